@@ -0,0 +1,5 @@
+fn main() {
+    println!("cargo:rerun-if-changed=src/detection/backend/dlib.rs");
+
+    cpp_build::Config::new().build("src/lib.rs");
+}
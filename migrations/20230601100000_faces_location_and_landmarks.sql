@@ -0,0 +1,5 @@
+ALTER TABLE faces ADD COLUMN box_left INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE faces ADD COLUMN box_top INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE faces ADD COLUMN box_right INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE faces ADD COLUMN box_bottom INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE faces ADD COLUMN landmarks TEXT NOT NULL DEFAULT '[]';
@@ -0,0 +1 @@
+ALTER TABLE faces ADD COLUMN confidence REAL;
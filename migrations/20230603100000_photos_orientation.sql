@@ -0,0 +1 @@
+ALTER TABLE photos ADD COLUMN orientation INTEGER NOT NULL DEFAULT 1;
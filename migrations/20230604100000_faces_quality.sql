@@ -0,0 +1,7 @@
+ALTER TABLE faces ADD COLUMN yaw REAL;
+ALTER TABLE faces ADD COLUMN pitch REAL;
+ALTER TABLE faces ADD COLUMN roll REAL;
+ALTER TABLE faces ADD COLUMN blur REAL;
+ALTER TABLE faces ADD COLUMN inter_ocular_distance REAL;
+ALTER TABLE faces ADD COLUMN brightness REAL;
+ALTER TABLE faces ADD COLUMN quality REAL;
@@ -0,0 +1 @@
+ALTER TABLE photos ADD COLUMN format VARCHAR NOT NULL DEFAULT 'jpeg';
@@ -0,0 +1,6 @@
+ALTER TABLE photos ADD COLUMN detector VARCHAR;
+ALTER TABLE photos ADD COLUMN detection_parameters TEXT;
+ALTER TABLE photos ADD COLUMN detection_duration REAL;
+ALTER TABLE photos ADD COLUMN face_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE photos ADD COLUMN detection_status VARCHAR;
+ALTER TABLE photos ADD COLUMN detection_error TEXT;
@@ -0,0 +1 @@
+ALTER TABLE faces ADD COLUMN encoder VARCHAR NOT NULL DEFAULT 'dlib_resnet@1';
@@ -0,0 +1,3 @@
+ALTER TABLE faces ADD COLUMN verdict VARCHAR;
+ALTER TABLE faces ADD COLUMN verdict_confidence REAL;
+ALTER TABLE faces ADD COLUMN verdict_candidates TEXT NOT NULL DEFAULT '[]';
@@ -0,0 +1,8 @@
+ALTER TABLE persons ADD COLUMN max_distance REAL;
+ALTER TABLE photos ADD COLUMN source VARCHAR;
+ALTER TABLE faces ADD COLUMN threshold REAL;
+CREATE TABLE IF NOT EXISTS sources (
+                              id INTEGER PRIMARY KEY,
+                              name VARCHAR NOT NULL UNIQUE,
+                              max_distance REAL NOT NULL
+                             );
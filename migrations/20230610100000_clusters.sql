@@ -0,0 +1,6 @@
+ALTER TABLE faces ADD COLUMN cluster_uuid VARCHAR;
+CREATE TABLE IF NOT EXISTS clusters (
+                              id INTEGER PRIMARY KEY,
+                              uuid VARCHAR NOT NULL,
+                              face_count INTEGER NOT NULL
+                             );
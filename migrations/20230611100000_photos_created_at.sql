@@ -0,0 +1 @@
+ALTER TABLE photos ADD COLUMN created_at DATETIME;
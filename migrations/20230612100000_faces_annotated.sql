@@ -0,0 +1 @@
+ALTER TABLE faces ADD COLUMN annotated BOOL NOT NULL DEFAULT false;
@@ -0,0 +1,140 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use uuid::Uuid;
+
+use crate::clusterer::options::Options;
+use crate::db::cluster::Cluster;
+use crate::db::face::Face;
+use crate::db::person::Person;
+use crate::recognition::index::distance;
+
+pub mod options;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    // unlabelled faces considered
+    pub faces: usize,
+    pub clusters: usize,
+    // faces that ended up in a stored cluster
+    pub clustered: usize,
+}
+
+pub fn call() -> Summary {
+    call_with_options(&Options::from_env())
+}
+
+// replaces stored clusters with clusters of unlabelled faces: neither moderated nor
+// recognized as a person, recognized ones are confirmed through review instead.
+// Embeddings of different models are never linked
+pub fn call_with_options(options: &Options) -> Summary {
+    let mut summary = Summary::default();
+    Cluster::clear();
+
+    let mut faces_by_encoder: BTreeMap<String, Vec<(String, Vec<f64>)>> = BTreeMap::new();
+    for face in Face::where_all(&format!(
+        "moderated IS NOT 'true' AND IFNULL(person_uuid, '') = '' AND (quality IS NULL OR quality >= {})",
+        options.min_quality
+    )) {
+        let measurements = face.deserialize_measurements();
+        if measurements.is_empty() {
+            continue;
+        }
+        faces_by_encoder
+            .entry(face.encoder.clone())
+            .or_default()
+            .push((face.uuid, measurements));
+    }
+
+    for faces in faces_by_encoder.values() {
+        summary.faces += faces.len();
+
+        let embeddings: Vec<&Vec<f64>> =
+            faces.iter().map(|(_, measurements)| measurements).collect();
+        let labels = chinese_whispers(&embeddings, options.max_distance, options.iterations);
+
+        let mut members: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for ((face_uuid, _), label) in faces.iter().zip(labels.into_iter()) {
+            members.entry(label).or_default().push(face_uuid.clone());
+        }
+
+        for face_uuids in members
+            .values()
+            .filter(|face_uuids| face_uuids.len() >= options.min_size)
+        {
+            Cluster::create(&Uuid::new_v4().to_string(), face_uuids);
+            summary.clusters += 1;
+            summary.clustered += face_uuids.len();
+        }
+    }
+    summary
+}
+
+// faces of the cluster are moderated as the person named `person_name`,
+// the person is created if there is none
+pub fn assign(cluster_uuid: &String, person_name: &String) -> (Person, usize) {
+    let person =
+        match Person::where_all(&format!("name = '{}'", person_name.replace('\'', "''"))).pop() {
+            Some(person) => person,
+            None => Person::create(&Uuid::new_v4().to_string(), person_name),
+        };
+    let assigned = Cluster::assign(cluster_uuid, &person.uuid);
+    (person, assigned)
+}
+
+// label per embedding, equal labels form a cluster. Embeddings closer than `max_distance`
+// are linked, closer ones weigh more; every embedding takes the label that weighs most
+// among its neighbours until labels settle
+pub fn chinese_whispers(
+    embeddings: &[&Vec<f64>],
+    max_distance: f64,
+    iterations: usize,
+) -> Vec<usize> {
+    let mut edges: Vec<Vec<(usize, f64)>> = vec![Vec::new(); embeddings.len()];
+    for i in 0..embeddings.len() {
+        for j in (i + 1)..embeddings.len() {
+            let face_distance = distance(embeddings[i], embeddings[j]);
+            if face_distance < max_distance {
+                let weight = 1.0 - face_distance / max_distance;
+                edges[i].push((j, weight));
+                edges[j].push((i, weight));
+            }
+        }
+    }
+
+    let mut labels: Vec<usize> = (0..embeddings.len()).collect();
+    for _ in 0..iterations {
+        let mut changed = false;
+        for i in 0..embeddings.len() {
+            let mut weights: HashMap<usize, f64> = HashMap::new();
+            for (j, weight) in edges[i].iter() {
+                *weights.entry(labels[*j]).or_insert(0.0) += weight;
+            }
+            // ties go to the lowest label, so results don't depend on hash order
+            let best = weights
+                .into_iter()
+                .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+                .map(|(label, _)| label);
+
+            if let Some(label) = best {
+                if label != labels[i] {
+                    labels[i] = label;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    labels
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Faces clustered: {} of {} into {} clusters",
+            self.clustered, self.faces, self.clusters
+        )
+    }
+}
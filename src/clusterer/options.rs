@@ -0,0 +1,43 @@
+use crate::detection::options::env_var;
+use dotenvy::dotenv;
+
+pub const DEFAULT_MAX_DISTANCE: f64 = 0.5;
+pub const DEFAULT_MIN_SIZE: usize = 2;
+pub const DEFAULT_MIN_QUALITY: f64 = 0.0;
+pub const DEFAULT_ITERATIONS: usize = 100;
+
+pub struct Options {
+    // faces closer than this are linked, stricter than recognition to keep clusters clean
+    pub max_distance: f64,
+    // smaller groups are not stored as clusters
+    pub min_size: usize,
+    // faces with lower quality score are not clustered
+    pub min_quality: f64,
+    // label propagation rounds, stops earlier once labels settle
+    pub iterations: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            max_distance: DEFAULT_MAX_DISTANCE,
+            min_size: DEFAULT_MIN_SIZE,
+            min_quality: DEFAULT_MIN_QUALITY,
+            iterations: DEFAULT_ITERATIONS,
+        }
+    }
+}
+
+impl Options {
+    pub fn from_env() -> Self {
+        dotenv().ok();
+        let defaults = Self::default();
+
+        Self {
+            max_distance: env_var("CLUSTERING_MAX_DISTANCE", defaults.max_distance),
+            min_size: env_var("CLUSTERING_MIN_SIZE", defaults.min_size),
+            min_quality: env_var("CLUSTERING_MIN_QUALITY", defaults.min_quality),
+            iterations: env_var("CLUSTERING_ITERATIONS", defaults.iterations),
+        }
+    }
+}
@@ -1,13 +1,15 @@
-use debug_print::debug_println;
+use debug_print::debug_eprintln;
 use dotenvy::dotenv;
 use sqlx::{migrate::MigrateDatabase, FromRow, Pool, Sqlite, SqlitePool};
 use std::env;
 use std::fs;
 use std::sync::Once;
 
+pub mod cluster;
 pub mod face;
 pub mod person;
 pub mod photo;
+pub mod source;
 
 static mut CONNECTION: Option<Pool<Sqlite>> = None;
 static INIT: Once = Once::new();
@@ -42,19 +44,19 @@ pub async fn prepare() -> Pool<Sqlite> {
         match fs::create_dir_all(database_folder) {
             Err(error) => panic!("Database folder cannot be created: {:?}", error),
             Ok(_) => {
-                debug_println!("Database folder is created")
+                debug_eprintln!("Database folder is created")
             }
         }
 
-        debug_println!("Creating database {}", &database_url);
+        debug_eprintln!("Creating database {}", &database_url);
         match Sqlite::create_database(&database_url).await {
             Ok(_) => {
-                debug_println!("Create db success")
+                debug_eprintln!("Create db success")
             }
             Err(error) => panic!("error: {}", error),
         }
     } else {
-        debug_println!("Database already exists");
+        debug_eprintln!("Database already exists");
     }
 
     let db = SqlitePool::connect(&database_url).await.unwrap();
@@ -68,13 +70,13 @@ pub async fn prepare() -> Pool<Sqlite> {
         .await;
     match migration_results {
         Ok(_) => {
-            debug_println!("Migration success")
+            debug_eprintln!("Migration success")
         }
         Err(error) => {
             panic!("error: {}", error);
         }
     }
-    debug_println!("migration: {:?}", migration_results);
+    debug_eprintln!("migration: {:?}", migration_results);
 
     db
 }
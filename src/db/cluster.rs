@@ -0,0 +1,168 @@
+use crate::db::face::Face;
+use crate::db::*;
+use colored::Colorize;
+use debug_print::debug_eprintln;
+use sqlx::FromRow;
+
+// unlabelled faces likely of the same person, found by clustering
+#[derive(Clone, FromRow, Debug, PartialEq)]
+pub struct Cluster {
+    pub id: i64,
+    pub uuid: String,
+    // faces at the time of clustering
+    pub face_count: i64,
+}
+
+impl Cluster {
+    fn table_name() -> String {
+        String::from("clusters")
+    }
+
+    pub fn create(uuid: &String, face_uuids: &Vec<String>) -> Self {
+        Self::insert(uuid, face_uuids.len() as i64);
+        Self::update_faces(uuid, face_uuids);
+        Self::find(uuid)
+    }
+
+    #[tokio::main]
+    async fn insert(uuid: &String, face_count: i64) {
+        let result = sqlx::query(&format!(
+            "INSERT INTO {table_name} (uuid, face_count) VALUES (?, ?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(uuid)
+        .bind(face_count)
+        .execute(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+            }
+        }
+    }
+
+    #[tokio::main]
+    async fn update_faces(uuid: &String, face_uuids: &Vec<String>) {
+        let placeholders = vec!["?"; face_uuids.len()].join(", ");
+        let query_str = format!(
+            "UPDATE faces SET cluster_uuid = ? WHERE uuid IN ({})",
+            placeholders
+        );
+
+        debug_eprintln!("{}", query_str.cyan());
+
+        let mut query = sqlx::query(&query_str).bind(uuid);
+        for face_uuid in face_uuids.iter() {
+            query = query.bind(face_uuid);
+        }
+        let result = query.execute(connection()).await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+            }
+        }
+    }
+
+    #[tokio::main]
+    pub async fn find(uuid: &String) -> Self {
+        let result = sqlx::query_as::<_, Self>(&format!(
+            "SELECT * FROM {table_name} WHERE uuid = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(uuid)
+        .fetch_one(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+
+                data
+            }
+        }
+    }
+
+    // largest first
+    #[tokio::main]
+    pub async fn all() -> Vec<Self> {
+        let result = sqlx::query_as::<_, Self>(&format!(
+            "SELECT * FROM {table_name} ORDER BY face_count DESC",
+            table_name = &Self::table_name()
+        ))
+        .fetch_all(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+
+                data
+            }
+        }
+    }
+
+    // faces moderated since clustering are left out
+    pub fn faces(&self) -> Vec<Face> {
+        Face::where_all(&format!(
+            "cluster_uuid = '{}' AND moderated IS NOT 'true'",
+            self.uuid
+        ))
+    }
+
+    // moderates every face of the cluster as the person, the cluster is dropped
+    pub fn assign(uuid: &String, person_uuid: &String) -> usize {
+        let faces = Self::find(uuid).faces();
+        for face in faces.iter() {
+            Face::moderate_person(&face.uuid, person_uuid);
+        }
+        Self::delete(uuid);
+        faces.len()
+    }
+
+    #[tokio::main]
+    pub async fn delete(uuid: &String) -> bool {
+        let result = sqlx::query(&format!(
+            "UPDATE faces SET cluster_uuid = NULL WHERE cluster_uuid = (?); DELETE FROM {table_name} WHERE uuid = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(uuid)
+        .bind(uuid)
+        .execute(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+
+                true
+            }
+        }
+    }
+
+    // drops every cluster before faces are clustered again
+    #[tokio::main]
+    pub async fn clear() -> bool {
+        let result = sqlx::query(&format!(
+            "UPDATE faces SET cluster_uuid = NULL; DELETE FROM {table_name}",
+            table_name = &Self::table_name()
+        ))
+        .execute(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+
+                true
+            }
+        }
+    }
+}
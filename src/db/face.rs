@@ -1,6 +1,10 @@
 use crate::db::*;
+use crate::detection::face_image::FaceImage;
+use crate::recognition::index;
+use crate::recognition::verdict::Verdict;
+use dlib_face_recognition::Rectangle;
 use colored::Colorize;
-use debug_print::debug_println;
+use debug_print::debug_eprintln;
 use serde_json::json;
 use sqlx::FromRow;
 
@@ -11,6 +15,32 @@ pub struct Face {
     pub photo_uuid: String,
     pub person_uuid: String,
     pub measurements: String,
+    // model that produced measurements
+    pub encoder: String,
+    pub box_left: i64,
+    pub box_top: i64,
+    pub box_right: i64,
+    pub box_bottom: i64,
+    pub landmarks: String,
+    pub confidence: Option<f64>,
+    pub yaw: Option<f64>,
+    pub pitch: Option<f64>,
+    pub roll: Option<f64>,
+    pub blur: Option<f64>,
+    pub inter_ocular_distance: Option<f64>,
+    pub brightness: Option<f64>,
+    pub quality: Option<f64>,
+    // recognition outcome: known, ambiguous or unknown, blank if not recognized yet
+    pub verdict: String,
+    pub verdict_confidence: Option<f64>,
+    // uuids of ambiguous persons, as JSON
+    pub verdict_candidates: String,
+    // distance threshold the verdict was decided with
+    pub threshold: Option<f64>,
+    // group of likely-same-person unlabelled faces, blank if not clustered
+    pub cluster_uuid: String,
+    // drawn by hand, kept by reprocessing even when the detector misses it
+    pub annotated: bool,
 }
 
 impl Face {
@@ -29,34 +59,225 @@ impl Face {
         }
     }
 
+    pub fn serialize_landmarks(data: &Vec<(i64, i64)>) -> String {
+        json!(data).to_string()
+    }
+
+    pub fn deserialize_landmarks(&self) -> Vec<(i64, i64)> {
+        match serde_json::from_str::<Vec<(i64, i64)>>(&self.landmarks.to_string()) {
+            Err(error) => panic!("deserialize_landmarks failed: {}", error),
+            Ok(json) => json,
+        }
+    }
+
+    pub fn location(&self) -> Rectangle {
+        Rectangle {
+            left: self.box_left,
+            top: self.box_top,
+            right: self.box_right,
+            bottom: self.box_bottom,
+        }
+    }
+
+    pub fn create(photo_uuid: &String, face: &FaceImage) -> Self {
+        Self::insert(photo_uuid, face);
+        let created = Self::find(&face.uuid);
+        index::created(&created);
+        created
+    }
+
     #[tokio::main]
-    pub async fn create(uuid: &String, photo_uuid: &String, measurements: &String) -> Self {
+    async fn insert(photo_uuid: &String, face: &FaceImage) {
+        let location = match face.face_location {
+            Some(rect) => rect,
+            None => panic!("Face location is missing: {}", face.uuid),
+        };
+
         let result = sqlx::query(&format!(
-            "INSERT INTO {table_name} (uuid, photo_uuid, measurements) VALUES (?, ?, ?)",
+            "INSERT INTO {table_name} (uuid, photo_uuid, measurements, encoder, box_left, box_top, box_right, box_bottom, landmarks, confidence, yaw, pitch, roll, blur, inter_ocular_distance, brightness, quality) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             table_name = &Self::table_name()
         ))
-        .bind(uuid)
+        .bind(&face.uuid)
         .bind(photo_uuid)
-        .bind(measurements)
+        .bind(Self::serialize_measurements(&face.measurements))
+        .bind(&face.encoder)
+        .bind(location.left)
+        .bind(location.top)
+        .bind(location.right)
+        .bind(location.bottom)
+        .bind(Self::serialize_landmarks(&face.landmarks))
+        .bind(face.confidence)
+        .bind(face.quality.map(|quality| quality.yaw))
+        .bind(face.quality.map(|quality| quality.pitch))
+        .bind(face.quality.map(|quality| quality.roll))
+        .bind(face.quality.map(|quality| quality.blur))
+        .bind(face.quality.map(|quality| quality.inter_ocular_distance))
+        .bind(face.quality.map(|quality| quality.brightness))
+        .bind(face.quality.map(|quality| quality.score))
+        .execute(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+            }
+        }
+    }
+
+    // measurements of a face found again by a newer detection run, moderation is kept.
+    // The face leaves its cluster, and recognition of an unmoderated face is cleared
+    // as it was decided with the old measurements
+    pub fn update_detection(face: &FaceImage) {
+        Self::save_detection(face);
+        index::measured(&Self::find(&face.uuid));
+    }
+
+    #[tokio::main]
+    async fn save_detection(face: &FaceImage) {
+        let location = match face.face_location {
+            Some(rect) => rect,
+            None => panic!("Face location is missing: {}", face.uuid),
+        };
+
+        let result = sqlx::query(&format!(
+            "UPDATE {table_name} SET measurements = ?, encoder = ?, box_left = ?, box_top = ?, box_right = ?, box_bottom = ?, landmarks = ?, confidence = ?, yaw = ?, pitch = ?, roll = ?, blur = ?, inter_ocular_distance = ?, brightness = ?, quality = ?, cluster_uuid = NULL WHERE uuid = (?); UPDATE {table_name} SET person_uuid = NULL, verdict = NULL, verdict_confidence = NULL, verdict_candidates = '[]', threshold = NULL WHERE uuid = (?) AND moderated IS NOT 'true'",
+            table_name = &Self::table_name()
+        ))
+        .bind(Self::serialize_measurements(&face.measurements))
+        .bind(&face.encoder)
+        .bind(location.left)
+        .bind(location.top)
+        .bind(location.right)
+        .bind(location.bottom)
+        .bind(Self::serialize_landmarks(&face.landmarks))
+        .bind(face.confidence)
+        .bind(face.quality.map(|quality| quality.yaw))
+        .bind(face.quality.map(|quality| quality.pitch))
+        .bind(face.quality.map(|quality| quality.roll))
+        .bind(face.quality.map(|quality| quality.blur))
+        .bind(face.quality.map(|quality| quality.inter_ocular_distance))
+        .bind(face.quality.map(|quality| quality.brightness))
+        .bind(face.quality.map(|quality| quality.score))
+        .bind(&face.uuid)
+        .bind(&face.uuid)
+        .execute(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+            }
+        }
+    }
+
+    #[tokio::main]
+    pub async fn update_annotated(uuid: &String) {
+        let result = sqlx::query(&format!(
+            "UPDATE {table_name} SET annotated = true WHERE uuid = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(uuid)
+        .execute(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+            }
+        }
+    }
+
+    // measurements of a face by another encoder, with the landmarks they were encoded from
+    pub fn update_encoding(
+        uuid: &String,
+        measurements: &Vec<f64>,
+        landmarks: &Vec<(i64, i64)>,
+        encoder: &String,
+    ) {
+        Self::save_encoding(uuid, measurements, landmarks, encoder);
+        index::measured(&Self::find(uuid));
+    }
+
+    #[tokio::main]
+    async fn save_encoding(
+        uuid: &String,
+        measurements: &Vec<f64>,
+        landmarks: &Vec<(i64, i64)>,
+        encoder: &String,
+    ) {
+        let result = sqlx::query(&format!(
+            "UPDATE {table_name} SET measurements = ?, landmarks = ?, encoder = ? WHERE uuid = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(Self::serialize_measurements(measurements))
+        .bind(Self::serialize_landmarks(landmarks))
+        .bind(encoder)
+        .bind(uuid)
         .execute(connection())
         .await;
 
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
-
-                Self {
-                    id: data.last_insert_rowid(),
-                    uuid: uuid.clone(),
-                    photo_uuid: photo_uuid.clone(),
-                    person_uuid: "".to_string(),
-                    measurements: measurements.clone(),
-                }
+                debug_eprintln!("Query result: {:?}", data);
             }
         }
     }
 
+    #[tokio::main]
+    pub async fn update_verdict(uuid: &String, verdict: &Verdict, threshold: Option<f64>) {
+        let (confidence, candidates) = match verdict {
+            Verdict::Known(_, confidence) => (Some(*confidence), Vec::new()),
+            Verdict::Ambiguous(persons) => (
+                None,
+                persons.iter().map(|person| person.uuid.clone()).collect(),
+            ),
+            Verdict::Unknown => (None, Vec::new()),
+        };
+
+        let result = sqlx::query(&format!(
+            "UPDATE {table_name} SET verdict = ?, verdict_confidence = ?, verdict_candidates = ?, threshold = ? WHERE uuid = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(verdict.name())
+        .bind(confidence)
+        .bind(json!(candidates).to_string())
+        .bind(threshold)
+        .bind(uuid)
+        .execute(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+            }
+        }
+    }
+
+    // stored recognition outcome, none if the face wasn't recognized yet
+    pub fn verdict(&self) -> Option<Verdict> {
+        match self.verdict.as_str() {
+            // person could have been deleted since
+            "known" => self.person().map(|person| {
+                Verdict::Known(person, self.verdict_confidence.unwrap_or(0.0))
+            }),
+            "ambiguous" => {
+                let uuids: Vec<String> = serde_json::from_str(&self.verdict_candidates).unwrap_or_default();
+                let uuids: Vec<String> = uuids.iter().map(|uuid| format!("'{}'", uuid)).collect();
+                Some(Verdict::Ambiguous(crate::db::person::Person::where_all(&format!(
+                    "uuid IN({})",
+                    uuids.join(", ")
+                ))))
+            }
+            "unknown" => Some(Verdict::Unknown),
+            _ => None,
+        }
+    }
+
     #[tokio::main]
     pub async fn update_person_uuid(uuid: &String, person_uuid: &String) -> bool {
         let result = sqlx::query(&format!(
@@ -71,22 +292,21 @@ impl Face {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
 
                 true
             }
         }
     }
 
+    // values are bound, so they can be passed as they are
     #[tokio::main]
     pub async fn update(uuid: &String, params: Vec<(&String, &String)>) -> bool {
         let mut statement = String::from("");
         for (i, param) in params.iter().enumerate() {
             let separator = if i < 1 { "" } else { ", " };
             statement.push_str(separator);
-            statement.push_str(
-                format!("{column} = '{value}'", column = param.0, value = param.1).as_str(),
-            );
+            statement.push_str(format!("{column} = ?", column = param.0).as_str());
         }
 
         let query_str = &format!(
@@ -95,17 +315,18 @@ impl Face {
             table_name = &Self::table_name()
         );
 
-        debug_println!("{}", query_str.cyan());
+        debug_eprintln!("{}", query_str.cyan());
 
-        let result = sqlx::query(query_str)
-            .bind(uuid)
-            .execute(connection())
-            .await;
+        let mut query = sqlx::query(query_str);
+        for (_, value) in params.iter() {
+            query = query.bind(*value);
+        }
+        let result = query.bind(uuid).execute(connection()).await;
 
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
 
                 true
             }
@@ -125,7 +346,27 @@ impl Face {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
+
+                data
+            }
+        }
+    }
+
+    #[tokio::main]
+    pub async fn find_by_uuid(uuid: &String) -> Option<Self> {
+        let result = sqlx::query_as::<_, Self>(&format!(
+            "SELECT * FROM {table_name} WHERE uuid = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(uuid)
+        .fetch_optional(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
 
                 data
             }
@@ -144,7 +385,7 @@ impl Face {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
 
                 data
             }
@@ -159,7 +400,7 @@ impl Face {
             table_name = &Self::table_name()
         );
 
-        debug_println!("{}", query_str.cyan());
+        debug_eprintln!("{}", query_str.cyan());
 
         let result = sqlx::query_as::<_, Self>(query_str)
             .fetch_all(connection())
@@ -168,7 +409,7 @@ impl Face {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
 
                 data
             }
@@ -188,7 +429,8 @@ impl Face {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
+                index::deleted(uuid);
 
                 true
             }
@@ -223,5 +465,6 @@ impl Face {
         let person_uuid = (&"person_uuid".to_string(), recognized_person_uuid);
         let update_params = vec![moderated, person_uuid];
         crate::db::face::Face::update(&face_uuid, update_params);
+        index::moderated(&Self::find(face_uuid));
     }
 }
@@ -1,13 +1,17 @@
 use crate::db::*;
 use colored::Colorize;
-use debug_print::debug_println;
+use debug_print::debug_eprintln;
+use serde::Serialize;
 use sqlx::FromRow;
+use std::error::Error;
 
-#[derive(Clone, FromRow, Debug, PartialEq)]
+#[derive(Clone, FromRow, Debug, PartialEq, Serialize)]
 pub struct Person {
     pub id: i64,
     pub uuid: String,
     pub name: String,
+    // overrides the recognition threshold, e.g. stricter for siblings
+    pub max_distance: Option<f64>,
 }
 
 impl Person {
@@ -29,17 +33,82 @@ impl Person {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
 
                 Self {
                     id: data.last_insert_rowid(),
                     uuid: uuid.clone(),
                     name: name.clone(),
+                    max_distance: None,
                 }
             }
         }
     }
 
+    // none falls back to the source or global threshold
+    pub fn set_max_distance(uuid: &String, max_distance: Option<f64>) -> Self {
+        Self::update_max_distance(uuid, max_distance);
+        let person = Self::find(uuid);
+        crate::recognition::index::person_changed(&person);
+        person
+    }
+
+    #[tokio::main]
+    async fn update_max_distance(uuid: &String, max_distance: Option<f64>) {
+        let result = sqlx::query(&format!(
+            "UPDATE {table_name} SET max_distance = ? WHERE uuid = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(max_distance)
+        .bind(uuid)
+        .execute(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+            }
+        }
+    }
+
+    // faces of the other person are moved to the person, the other person is deleted.
+    // Nothing is changed unless both persons exist and differ
+    pub fn merge(uuid: &String, other_uuid: &String) -> Result<u64, Box<dyn Error>> {
+        if uuid == other_uuid {
+            return Err(format!("Person cannot be merged into itself: {}", uuid).into());
+        }
+        let Some(person) = Self::find_by_uuid(uuid) else {
+            return Err(format!("Person not found: {}", uuid).into());
+        };
+        if Self::find_by_uuid(other_uuid).is_none() {
+            return Err(format!("Person not found: {}", other_uuid).into());
+        }
+
+        let moved = Self::move_faces(other_uuid, uuid);
+        crate::recognition::index::person_merged(&person, other_uuid);
+        Self::delete(other_uuid);
+        Ok(moved)
+    }
+
+    #[tokio::main]
+    async fn move_faces(from_uuid: &String, to_uuid: &String) -> u64 {
+        let result = sqlx::query("UPDATE faces SET person_uuid = ? WHERE person_uuid = (?)")
+            .bind(to_uuid)
+            .bind(from_uuid)
+            .execute(connection())
+            .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+
+                data.rows_affected()
+            }
+        }
+    }
+
     #[tokio::main]
     pub async fn find(uuid: &String) -> Self {
         let result = sqlx::query_as::<_, Self>(&format!(
@@ -53,7 +122,27 @@ impl Person {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
+
+                data
+            }
+        }
+    }
+
+    #[tokio::main]
+    pub async fn find_by_uuid(uuid: &String) -> Option<Self> {
+        let result = sqlx::query_as::<_, Self>(&format!(
+            "SELECT * FROM {table_name} WHERE uuid = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(uuid)
+        .fetch_optional(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
 
                 data
             }
@@ -72,7 +161,7 @@ impl Person {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
 
                 data
             }
@@ -87,7 +176,7 @@ impl Person {
             table_name = &Self::table_name()
         );
 
-        debug_println!("{}", query_str.cyan());
+        debug_eprintln!("{}", query_str.cyan());
 
         let result = sqlx::query_as::<_, Self>(query_str)
             .fetch_all(connection())
@@ -96,7 +185,7 @@ impl Person {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
 
                 data
             }
@@ -116,7 +205,8 @@ impl Person {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
+                crate::recognition::index::person_deleted(uuid);
 
                 true
             }
@@ -1,13 +1,27 @@
 use crate::db;
 use crate::db::*;
+use crate::detection::status::Status;
 use colored::Colorize;
-use debug_print::debug_println;
+use debug_print::debug_eprintln;
 use sqlx::FromRow;
 
 #[derive(Clone, FromRow, Debug)]
 pub struct Photo {
     pub id: i64,
     pub uuid: String,
+    pub orientation: i64,
+    pub format: String,
+    // blank for photos stored before detection runs were recorded
+    pub detector: String,
+    pub detection_parameters: String,
+    pub detection_duration: Option<f64>,
+    pub face_count: i64,
+    pub detection_status: String,
+    pub detection_error: String,
+    // blank unless recognition ran with a source
+    pub source: String,
+    // UTC, "YYYY-MM-DD HH:MM:SS", blank for photos stored before it was recorded
+    pub created_at: String,
 }
 
 impl Photo {
@@ -17,19 +31,119 @@ impl Photo {
 
     pub fn save_detection_result(photo: &crate::detection::photo::Photo) {
         crate::db::photo::Photo::create(&photo.uuid);
+        crate::db::photo::Photo::update(
+            &photo.uuid,
+            vec![
+                (&"orientation".to_string(), &photo.orientation.to_string()),
+                (
+                    &"format".to_string(),
+                    &crate::media::name(photo.format).to_string(),
+                ),
+            ],
+        );
+        Self::save_detection_run(&photo.uuid, photo);
         for face in photo.faces.iter() {
-            crate::db::face::Face::create(
-                &face.uuid,
-                &photo.uuid,
-                &crate::db::face::Face::serialize_measurements(&face.measurements),
-            );
+            crate::db::face::Face::create(&photo.uuid, face);
+        }
+    }
+
+    // details of a detection run over the stored photo `uuid`
+    pub fn save_detection_run(uuid: &String, photo: &crate::detection::photo::Photo) {
+        Self::update_detection(
+            uuid,
+            &photo.detector,
+            &photo.parameters,
+            Some(photo.duration),
+            photo.faces.len() as i64,
+            photo.status(),
+            None,
+        );
+    }
+
+    // stored photo that could not be processed by `detector`
+    pub fn save_detection_error(uuid: &String, detector: &String, parameters: &String, error: &String) {
+        Self::update_detection(uuid, detector, parameters, None, 0, Status::Error, Some(error));
+    }
+
+    #[tokio::main]
+    async fn update_detection(
+        uuid: &String,
+        detector: &String,
+        parameters: &String,
+        duration: Option<f64>,
+        face_count: i64,
+        status: Status,
+        error: Option<&String>,
+    ) {
+        let result = sqlx::query(&format!(
+            "UPDATE {table_name} SET detector = ?, detection_parameters = ?, detection_duration = ?, face_count = ?, detection_status = ?, detection_error = ? WHERE uuid = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(detector)
+        .bind(parameters)
+        .bind(duration)
+        .bind(face_count)
+        .bind(status.name())
+        .bind(error)
+        .bind(uuid)
+        .execute(db::connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+            }
         }
     }
 
+    // counts faces stored for the photo `uuid`, including faces drawn by hand or kept by
+    // reprocessing. A photo without faces becomes ok once it has one
+    #[tokio::main]
+    pub async fn update_face_count(uuid: &String) {
+        let result = sqlx::query(&format!(
+            "UPDATE {table_name} SET face_count = (SELECT COUNT(*) FROM faces WHERE faces.photo_uuid = {table_name}.uuid) WHERE uuid = (?);
+            UPDATE {table_name} SET detection_status = ? WHERE uuid = (?) AND face_count > 0 AND detection_status = ?",
+            table_name = &Self::table_name()
+        ))
+        .bind(uuid)
+        .bind(Status::Ok.name())
+        .bind(uuid)
+        .bind(Status::NoFaces.name())
+        .execute(db::connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+            }
+        }
+    }
+
+    // photos never processed, failed, without faces or processed by another detector
+    pub fn needing_detection(detector: &String) -> Vec<Self> {
+        Self::where_all(&format!(
+            "detection_status IS NULL OR detection_status IN('{}', '{}') OR detector IS NOT '{}'",
+            Status::Error.name(),
+            Status::NoFaces.name(),
+            detector.replace('\'', "''")
+        ))
+    }
+
+    pub fn status(&self) -> Option<Status> {
+        Status::from_name(&self.detection_status)
+    }
+
+    pub fn create(uuid: &String) -> Self {
+        Self::insert(uuid);
+        Self::find(uuid)
+    }
+
     #[tokio::main]
-    pub async fn create(uuid: &String) -> Self {
+    async fn insert(uuid: &String) {
         let result = sqlx::query(&format!(
-            "INSERT INTO {table_name} (uuid) VALUES (?)",
+            "INSERT INTO {table_name} (uuid, created_at) VALUES (?, datetime('now'))",
             table_name = &Self::table_name()
         ))
         .bind(uuid)
@@ -39,12 +153,41 @@ impl Photo {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
+            }
+        }
+    }
+
+    // values are bound, so they can be passed as they are
+    #[tokio::main]
+    pub async fn update(uuid: &String, params: Vec<(&String, &String)>) -> bool {
+        let mut statement = String::from("");
+        for (i, param) in params.iter().enumerate() {
+            let separator = if i < 1 { "" } else { ", " };
+            statement.push_str(separator);
+            statement.push_str(format!("{column} = ?", column = param.0).as_str());
+        }
+
+        let query_str = &format!(
+            "UPDATE {table_name} SET {} WHERE uuid = (?)",
+            statement,
+            table_name = &Self::table_name()
+        );
+
+        debug_eprintln!("{}", query_str.cyan());
+
+        let mut query = sqlx::query(query_str);
+        for (_, value) in params.iter() {
+            query = query.bind(*value);
+        }
+        let result = query.bind(uuid).execute(db::connection()).await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
 
-                Self {
-                    id: data.last_insert_rowid(),
-                    uuid: uuid.clone(),
-                }
+                true
             }
         }
     }
@@ -62,7 +205,27 @@ impl Photo {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
+
+                data
+            }
+        }
+    }
+
+    #[tokio::main]
+    pub async fn find_by_uuid(uuid: &String) -> Option<Self> {
+        let result = sqlx::query_as::<_, Self>(&format!(
+            "SELECT * FROM {table_name} WHERE uuid = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(uuid)
+        .fetch_optional(db::connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
 
                 data
             }
@@ -81,7 +244,7 @@ impl Photo {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
 
                 data
             }
@@ -96,7 +259,7 @@ impl Photo {
             table_name = &Self::table_name()
         );
 
-        debug_println!("{}", query_str.cyan());
+        debug_eprintln!("{}", query_str.cyan());
 
         let result = sqlx::query_as::<_, Self>(query_str)
             .fetch_all(db::connection())
@@ -105,7 +268,7 @@ impl Photo {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
 
                 data
             }
@@ -125,7 +288,7 @@ impl Photo {
         match result {
             Err(error) => panic!("error: {}", error),
             Ok(data) => {
-                debug_println!("Query result: {:?}", data);
+                debug_eprintln!("Query result: {:?}", data);
 
                 true
             }
@@ -147,6 +310,11 @@ impl Photo {
         }
     }
 
+    pub fn original_file_name(&self) -> String {
+        let format = crate::media::from_name(&self.format).unwrap_or(image::ImageFormat::Jpeg);
+        crate::storage::original_file_name(format)
+    }
+
     pub fn faces(&self) -> Vec<crate::db::face::Face> {
         crate::db::face::Face::where_all(&format!("photo_uuid = '{}'", self.uuid))
     }
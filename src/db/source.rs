@@ -0,0 +1,103 @@
+use crate::db::*;
+use colored::Colorize;
+use debug_print::debug_eprintln;
+use serde::Serialize;
+use sqlx::FromRow;
+
+// camera feed or any other origin of photos with its own recognition threshold
+#[derive(Clone, FromRow, Debug, PartialEq, Serialize)]
+pub struct Source {
+    pub id: i64,
+    pub name: String,
+    pub max_distance: f64,
+}
+
+impl Source {
+    fn table_name() -> String {
+        String::from("sources")
+    }
+
+    // creates the source or updates its threshold
+    pub fn set(name: &String, max_distance: f64) -> Self {
+        Self::upsert(name, max_distance);
+        Self::find_by_name(name).unwrap()
+    }
+
+    #[tokio::main]
+    async fn upsert(name: &String, max_distance: f64) {
+        let result = sqlx::query(&format!(
+            "INSERT INTO {table_name} (name, max_distance) VALUES (?, ?) ON CONFLICT(name) DO UPDATE SET max_distance = excluded.max_distance",
+            table_name = &Self::table_name()
+        ))
+        .bind(name)
+        .bind(max_distance)
+        .execute(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+            }
+        }
+    }
+
+    #[tokio::main]
+    pub async fn find_by_name(name: &String) -> Option<Self> {
+        let result = sqlx::query_as::<_, Self>(&format!(
+            "SELECT * FROM {table_name} WHERE name = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(name)
+        .fetch_optional(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+
+                data
+            }
+        }
+    }
+
+    #[tokio::main]
+    pub async fn all() -> Vec<Self> {
+        let result = sqlx::query_as::<_, Self>(&format!(
+            "SELECT * FROM {table_name}",
+            table_name = &Self::table_name()
+        ))
+        .fetch_all(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+
+                data
+            }
+        }
+    }
+
+    #[tokio::main]
+    pub async fn delete(name: &String) -> bool {
+        let result = sqlx::query(&format!(
+            "DELETE FROM {table_name} WHERE name = (?)",
+            table_name = &Self::table_name()
+        ))
+        .bind(name)
+        .execute(connection())
+        .await;
+
+        match result {
+            Err(error) => panic!("error: {}", error),
+            Ok(data) => {
+                debug_eprintln!("Query result: {:?}", data);
+
+                true
+            }
+        }
+    }
+}
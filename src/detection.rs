@@ -1,90 +1,241 @@
-use dlib_face_recognition::FaceDetectorCnn;
-use dlib_face_recognition::*;
+use dlib_face_recognition::Rectangle;
 use image;
 use image::*;
 use std::error::Error;
+use std::time::Instant;
 
+use crate::detection::backend::{Detector, Encoder, LandmarkPredictor};
 use crate::detection::face_image::FaceImage;
+use crate::detection::options::Options;
 use crate::detection::photo::Photo;
+use crate::media;
 use crate::storage;
 
+pub mod backend;
 pub mod face_image;
+pub mod nms;
+pub mod options;
+pub mod orientation;
 pub mod photo;
+pub mod quality;
+pub mod status;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Detection {
+    pub location: Rectangle,
+    pub confidence: Option<f64>,
+}
 
 pub fn call(path: &String) -> Result<Photo, Box<dyn Error>> {
-    let mut image = image::open(path).unwrap();
-    let mut photo = Photo::new();
-    let folder_path: String = format!("{}/{}/", storage::IMAGES_DIR, photo.uuid);
+    call_with_options(path, &Options::from_env())
+}
 
-    storage::save_original_image(&folder_path, &image).expect("original image cannot be saved");
-    storage::save_thumbnail(&folder_path, &image).expect("original image cannot be saved");
+pub fn call_with_options(path: &String, options: &Options) -> Result<Photo, Box<dyn Error>> {
+    let format = match media::sniff(path) {
+        media::Kind::Image(format) => format,
+        media::Kind::AnimatedGif => return Err(format!("Animated gif is a video: {}", path).into()),
+        media::Kind::Unsupported => return Err(format!("Unsupported file: {}", path).into()),
+    };
+
+    let exif_orientation = orientation::read(path);
+    let image = orientation::apply(media::open(path)?, exif_orientation);
+    let mut photo = analyze(image, options);
+    photo.store_orientation(exif_orientation);
+    photo.store_format(format);
+
+    let stored_format = store(&photo, Some(path), options.crop_margin);
+    photo.store_format(stored_format);
+    Ok(photo)
+}
 
-    let face_locations = detect_faces(&image);
+// stores a photo analyzed from an image in memory, such as a frame of an animation
+pub fn store_analyzed(photo: &mut Photo, options: &Options) {
+    let stored_format = store(photo, None, options.crop_margin);
+    photo.store_format(stored_format);
+}
 
-    if face_locations.len() == 0 {
-        println!("Faces are not detected")
+// detects and measures faces, nothing is written to the storage
+pub fn analyze(image: DynamicImage, options: &Options) -> Photo {
+    let mut photo = Photo::new();
+    let start = Instant::now();
+
+    let detections: Vec<Detection> = options
+        .backend
+        .detector
+        .detect(&image)
+        .into_iter()
+        .filter(|detection| face_size(&detection.location) >= options.min_face_size)
+        .filter(|detection| is_confident(detection, options.min_confidence))
+        .collect();
+    let detections = nms::suppress(detections, options.nms_threshold);
+
+    if detections.len() == 0 {
+        // diagnostics go to stderr, stdout can carry JSON results
+        eprintln!("Faces are not detected")
     }
 
-    for rect in face_locations.iter() {
-        match crop_face(rect, &mut image, &folder_path) {
-            Err(error) => panic!("Face cannot be cropped: {:?}", error),
-            Ok(face_image) => photo.add_face(face_image),
-        }
+    for detection in detections.iter() {
+        let mut face_image = FaceImage::new();
+        face_image.store_face_location(detection.location);
+        face_image.store_confidence(detection.confidence);
+        photo.add_face(face_image);
     }
 
     photo.push_img(image);
-    measure_faces(&mut photo);
-    Ok(photo)
+    measure_faces(&mut photo, options);
+    photo.store_run(
+        options.backend.detector.name(),
+        options.parameters(),
+        start.elapsed().as_secs_f64(),
+    );
+    photo
 }
 
-fn crop_face(
-    rect: &Rectangle,
-    image: &mut DynamicImage,
-    folder_path: &String,
-) -> Result<FaceImage, ImageError> {
-    let width: u32 = (rect.right - rect.left).try_into().unwrap();
-    let height: u32 = (rect.bottom - rect.top).try_into().unwrap();
-    let sub_image = image::imageops::crop(image, rect.left as u32, rect.top as u32, width, height);
+// face missed by the detector and drawn by hand on a stored photo. It is measured the same way
+// as detected faces and its crop is saved next to the others
+pub fn annotate(
+    photo_uuid: &String,
+    original_file_name: &String,
+    location: Rectangle,
+    options: &Options,
+) -> Result<FaceImage, Box<dyn Error>> {
+    let folder_path: String = format!("{}/{}/", storage::IMAGES_DIR, photo_uuid);
+    let image = storage::open_original(photo_uuid, original_file_name)?;
+    let location = crop_area(&location, 0.0, image.width(), image.height());
+    if face_size(&location) == 0 {
+        return Err("Annotated face area is empty".into());
+    }
 
+    let mut photo = Photo::new();
     let mut face_image = FaceImage::new();
-    let path = format!("{}/{}.jpg", folder_path, face_image.uuid);
-    match sub_image.to_image().save(path.clone()) {
-        Err(error) => Err(error),
-        Ok(_file) => {
-            face_image.store_face_location(*rect);
-            Ok(face_image)
-        }
-    }
+    face_image.store_face_location(location);
+    photo.add_face(face_image);
+    photo.push_img(image);
+    measure_faces(&mut photo, options);
+
+    let face_image = photo.faces.pop().unwrap();
+    crop_face(
+        &face_image,
+        photo.image.as_ref().unwrap(),
+        &folder_path,
+        options.crop_margin,
+    )?;
+    Ok(face_image)
 }
 
-fn detect_faces(image: &DynamicImage) -> FaceLocations {
-    let matrix = ImageMatrix::from_image(&image.to_rgb8());
+// returns format of the saved original
+fn store(photo: &Photo, source_path: Option<&String>, crop_margin: f64) -> ImageFormat {
+    let image = photo.image.as_ref().unwrap();
+    let folder_path: String = format!("{}/{}/", storage::IMAGES_DIR, photo.uuid);
 
-    let Ok(cnn_detector) = FaceDetectorCnn::default() else {
-        panic!("Unable to load cnn face detector!");
-    };
+    // the file is kept byte to byte unless it had to be turned upright
+    let source = source_path.filter(|_| photo.orientation == orientation::NORMAL);
+    let format = storage::save_original_image(&folder_path, image, source, photo.format)
+        .expect("original image cannot be saved");
+    storage::save_thumbnail(&folder_path, image).expect("original image cannot be saved");
+    store_crops(&photo.uuid, photo, crop_margin);
 
-    cnn_detector.face_locations(&matrix)
+    format
 }
 
-fn measure_faces(photo: &mut Photo) {
-    let Ok(landmarks) = LandmarkPredictor::default() else {
-        panic!("Error loading Landmark Predictor.");
-    };
+// saves crops of `photo` faces into the folder of the stored photo `photo_uuid`
+pub fn store_crops(photo_uuid: &String, photo: &Photo, crop_margin: f64) {
+    let image = photo.image.as_ref().unwrap();
+    let folder_path: String = format!("{}/{}/", storage::IMAGES_DIR, photo_uuid);
 
-    let Ok(face_encoder) = FaceEncoderNetwork::default() else {
-        panic!("Error loading Face Encoder.");
-    };
+    for face in photo.faces.iter() {
+        if let Err(error) = crop_face(face, image, &folder_path, crop_margin) {
+            panic!("Face cannot be cropped: {:?}", error)
+        }
+    }
+}
 
-    let matrix = ImageMatrix::from_image(&photo.image.as_ref().unwrap().to_rgb8());
+fn crop_face(
+    face: &FaceImage,
+    image: &DynamicImage,
+    folder_path: &String,
+    margin: f64,
+) -> Result<(), ImageError> {
+    let area = crop_area(
+        &face.face_location.unwrap(),
+        margin,
+        image.width(),
+        image.height(),
+    );
+    let sub_image = image.crop_imm(
+        area.left as u32,
+        area.top as u32,
+        (area.right - area.left) as u32,
+        (area.bottom - area.top) as u32,
+    );
+
+    sub_image
+        .to_rgb8()
+        .save(format!("{}/{}.jpg", folder_path, face.uuid))
+}
 
-    for face in photo.faces.iter_mut() {
-        let landmarks = landmarks.face_landmarks(&matrix, &face.face_location.unwrap());
+// face box grown by `margin` percent on every side and clamped to the image
+pub fn crop_area(rect: &Rectangle, margin: f64, image_width: u32, image_height: u32) -> Rectangle {
+    let margin_x = ((rect.right - rect.left) as f64 * margin / 100.0).round() as i64;
+    let margin_y = ((rect.bottom - rect.top) as f64 * margin / 100.0).round() as i64;
+    let left = (rect.left - margin_x).max(0);
+    let top = (rect.top - margin_y).max(0);
+
+    Rectangle {
+        left: left,
+        top: top,
+        right: (rect.right + margin_x).min(image_width as i64).max(left),
+        bottom: (rect.bottom + margin_y).min(image_height as i64).max(top),
+    }
+}
 
-        let encodings = face_encoder.get_face_encodings(&matrix, &[landmarks], 0); // -> FaceEncodings
+pub fn face_size(rect: &Rectangle) -> u32 {
+    (rect.right - rect.left).min(rect.bottom - rect.top).max(0) as u32
+}
 
-        let face_measurements = encodings.first().unwrap();
+// detections without a score are kept: there is nothing to compare against
+pub fn is_confident(detection: &Detection, min_confidence: Option<f64>) -> bool {
+    match (detection.confidence, min_confidence) {
+        (Some(confidence), Some(min_confidence)) => confidence >= min_confidence,
+        _ => true,
+    }
+}
+
+pub fn format_score(score: Option<f64>) -> String {
+    match score {
+        Some(score) => format!("{:.2}", score),
+        None => String::from("n/a"),
+    }
+}
 
-        face.store_measurements(face_measurements.as_ref().to_vec());
+fn measure_faces(photo: &mut Photo, options: &Options) {
+    let image = photo.image.as_ref().unwrap();
+    let locations: Vec<Rectangle> = photo
+        .faces
+        .iter()
+        .map(|face| face.face_location.unwrap())
+        .collect();
+
+    let landmarks = options
+        .backend
+        .landmark_predictor
+        .landmarks(image, &locations);
+    let faces: Vec<(Rectangle, Vec<(i64, i64)>)> =
+        locations.into_iter().zip(landmarks.into_iter()).collect();
+    let encodings = options
+        .backend
+        .encoder
+        .encode(image, &faces, options.jitters);
+    let encoder = options.backend.encoder.model();
+
+    for (face, ((location, points), measurements)) in photo
+        .faces
+        .iter_mut()
+        .zip(faces.into_iter().zip(encodings.into_iter()))
+    {
+        face.store_measurements(measurements);
+        face.store_encoder(encoder.clone());
+        face.store_quality(quality::assess(image, &location, &points));
+        face.store_landmarks(points);
     }
 }
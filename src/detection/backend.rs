@@ -0,0 +1,66 @@
+use image::DynamicImage;
+use std::rc::Rc;
+
+use crate::detection::{Detection, Rectangle};
+
+pub mod dlib;
+pub mod fake;
+
+pub trait Detector {
+    // recorded with every processed photo
+    fn name(&self) -> String;
+
+    fn detect(&self, image: &DynamicImage) -> Vec<Detection>;
+}
+
+pub trait LandmarkPredictor {
+    // 68 points for every location, in the same order
+    fn landmarks(&self, image: &DynamicImage, locations: &[Rectangle]) -> Vec<Vec<(i64, i64)>>;
+}
+
+pub trait Encoder {
+    // "<model id>@<version>", embeddings of different models are not comparable
+    fn model(&self) -> String;
+
+    // one embedding for every (location, landmarks) pair, in the same order
+    fn encode(
+        &self,
+        image: &DynamicImage,
+        faces: &[(Rectangle, Vec<(i64, i64)>)],
+        jitters: u32,
+    ) -> Vec<Vec<f64>>;
+}
+
+#[derive(Clone)]
+pub struct Backend {
+    pub detector: Rc<dyn Detector>,
+    pub landmark_predictor: Rc<dyn LandmarkPredictor>,
+    pub encoder: Rc<dyn Encoder>,
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Self::dlib()
+    }
+}
+
+impl Backend {
+    // models are loaded on first use
+    pub fn dlib() -> Self {
+        Self {
+            detector: Rc::new(dlib::Detector::new()),
+            landmark_predictor: Rc::new(dlib::LandmarkPredictor::new()),
+            encoder: Rc::new(dlib::Encoder::new()),
+        }
+    }
+
+    pub fn fake(fake: fake::Fake) -> Self {
+        let fake = Rc::new(fake);
+
+        Self {
+            detector: fake.clone(),
+            landmark_predictor: fake.clone(),
+            encoder: fake,
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use cpp::{cpp, cpp_class};
+use dlib_face_recognition::*;
+use image::DynamicImage;
+use std::cell::OnceCell;
+use std::ffi::CString;
+use std::path::Path;
+
+use crate::detection::options::env_var;
+use crate::detection::{Detection, Rectangle};
+
+// dlib_face_recognition_resnet_model_v1
+pub const MODEL: &str = "dlib_resnet@1";
+
+// mmod_human_face_detector, see "Instalation" in README
+pub const DEFAULT_CNN_MODEL: &str = "storage/models/mmod_human_face_detector.dat";
+
+cpp! {{
+    #include <dlib/dnn.h>
+    #include <dlib/image_processing/full_object_detection.h>
+
+    // cnn face detector definition from
+    // https://github.com/davisking/dlib/blob/master/tools/python/src/cnn_face_detector.cpp#L121
+
+    template <long num_filters, typename SUBNET> using con5d = dlib::con<num_filters,5,5,2,2,SUBNET>;
+    template <long num_filters, typename SUBNET> using con5  = dlib::con<num_filters,5,5,1,1,SUBNET>;
+
+    template <typename SUBNET> using downsampler  = dlib::relu<dlib::affine<con5d<32, dlib::relu<dlib::affine<con5d<32, dlib::relu<dlib::affine<con5d<16,SUBNET>>>>>>>>>;
+    template <typename SUBNET> using rcon5  = dlib::relu<dlib::affine<con5<45,SUBNET>>>;
+
+    using face_detection_cnn = dlib::loss_mmod<dlib::con<1,9,9,1,1,rcon5<rcon5<rcon5<downsampler<dlib::input_rgb_image_pyramid<dlib::pyramid_down<6>>>>>>>>;
+}}
+
+// `FaceDetectorCnn` of dlib-face-recognition keeps only `mmod_rect::rect` of the network output,
+// so the network is bound here to read `mmod_rect::detection_confidence` too
+cpp_class!(unsafe struct CnnNetwork as "face_detection_cnn");
+cpp_class!(unsafe struct MmodRects as "std::vector<dlib::mmod_rect>");
+
+pub struct Detector {
+    model: OnceCell<CnnNetwork>,
+}
+
+pub struct LandmarkPredictor {
+    model: OnceCell<dlib_face_recognition::LandmarkPredictor>,
+}
+
+pub struct Encoder {
+    model: OnceCell<FaceEncoderNetwork>,
+}
+
+impl Detector {
+    pub fn new() -> Self {
+        Self {
+            model: OnceCell::new(),
+        }
+    }
+
+    fn model(&self) -> &CnnNetwork {
+        self.model.get_or_init(|| {
+            let path = env_var("DETECTION_CNN_MODEL", DEFAULT_CNN_MODEL.to_string());
+            if !Path::new(&path).exists() {
+                panic!("Cnn face detector model is missing: {}", path);
+            }
+
+            let filename = CString::new(path.as_str()).unwrap();
+            let filename = filename.as_ptr();
+            let network = CnnNetwork::default();
+            let network_ref = &network;
+            let deserialized = unsafe {
+                cpp!([filename as "const char*", network_ref as "face_detection_cnn*"] -> bool as "bool" {
+                    try {
+                        dlib::deserialize(filename) >> *network_ref;
+                        return true;
+                    } catch (const dlib::error& exception) {
+                        return false;
+                    }
+                })
+            };
+            if !deserialized {
+                panic!("Unable to load cnn face detector: {}", path);
+            }
+            network
+        })
+    }
+}
+
+impl super::Detector for Detector {
+    fn name(&self) -> String {
+        String::from("dlib_mmod_cnn")
+    }
+
+    fn detect(&self, image: &DynamicImage) -> Vec<Detection> {
+        let matrix = ImageMatrix::from_image(&image.to_rgb8());
+        let matrix = &matrix;
+        let network = self.model();
+
+        let detections = unsafe {
+            cpp!([network as "face_detection_cnn*", matrix as "dlib::matrix<dlib::rgb_pixel>*"] -> MmodRects as "std::vector<dlib::mmod_rect>" {
+                return (*network)(*matrix);
+            })
+        };
+        let detections = &detections;
+        let count = unsafe {
+            cpp!([detections as "std::vector<dlib::mmod_rect>*"] -> usize as "size_t" {
+                return detections->size();
+            })
+        };
+
+        (0..count)
+            .map(|i| unsafe {
+                Detection {
+                    location: cpp!([detections as "std::vector<dlib::mmod_rect>*", i as "size_t"] -> Rectangle as "dlib::rectangle" {
+                        return (*detections)[i].rect;
+                    }),
+                    confidence: Some(cpp!([detections as "std::vector<dlib::mmod_rect>*", i as "size_t"] -> f64 as "double" {
+                        return (*detections)[i].detection_confidence;
+                    })),
+                }
+            })
+            .collect()
+    }
+}
+
+impl LandmarkPredictor {
+    pub fn new() -> Self {
+        Self {
+            model: OnceCell::new(),
+        }
+    }
+
+    fn model(&self) -> &dlib_face_recognition::LandmarkPredictor {
+        self.model.get_or_init(|| {
+            let Ok(landmarks) = dlib_face_recognition::LandmarkPredictor::default() else {
+                panic!("Error loading Landmark Predictor.");
+            };
+            landmarks
+        })
+    }
+}
+
+impl super::LandmarkPredictor for LandmarkPredictor {
+    fn landmarks(&self, image: &DynamicImage, locations: &[Rectangle]) -> Vec<Vec<(i64, i64)>> {
+        let matrix = ImageMatrix::from_image(&image.to_rgb8());
+
+        locations
+            .iter()
+            .map(|location| {
+                self.model()
+                    .face_landmarks(&matrix, location)
+                    .iter()
+                    .map(|point| (point.x(), point.y()))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self {
+            model: OnceCell::new(),
+        }
+    }
+
+    fn model(&self) -> &FaceEncoderNetwork {
+        self.model.get_or_init(|| {
+            let Ok(face_encoder) = FaceEncoderNetwork::default() else {
+                panic!("Error loading Face Encoder.");
+            };
+            face_encoder
+        })
+    }
+}
+
+impl super::Encoder for Encoder {
+    fn model(&self) -> String {
+        String::from(MODEL)
+    }
+
+    fn encode(
+        &self,
+        image: &DynamicImage,
+        faces: &[(Rectangle, Vec<(i64, i64)>)],
+        jitters: u32,
+    ) -> Vec<Vec<f64>> {
+        let matrix = ImageMatrix::from_image(&image.to_rgb8());
+
+        faces
+            .iter()
+            .map(|(location, points)| {
+                let landmarks = face_landmarks(location, points);
+                let encodings = self
+                    .model()
+                    .get_face_encodings(&matrix, &[landmarks], jitters);
+
+                encodings.first().unwrap().as_ref().to_vec()
+            })
+            .collect()
+    }
+}
+
+// dlib encoder takes its own landmarks type, it is built from the predicted points
+fn face_landmarks(location: &Rectangle, points: &[(i64, i64)]) -> FaceLandmarks {
+    let parts: Vec<Point> = points.iter().map(|(x, y)| Point::new(*x, *y)).collect();
+    let parts = parts.as_ptr();
+    let count = points.len();
+
+    unsafe {
+        cpp!([location as "dlib::rectangle*", parts as "const dlib::point*", count as "size_t"] -> FaceLandmarks as "dlib::full_object_detection" {
+            return dlib::full_object_detection(*location, std::vector<dlib::point>(parts, parts + count));
+        })
+    }
+}
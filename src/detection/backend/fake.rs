@@ -0,0 +1,111 @@
+use image::DynamicImage;
+
+use crate::detection::{Detection, Rectangle};
+
+pub const EMBEDDING_SIZE: usize = 128;
+
+// deterministic backend for tests: returns scripted detections for any image,
+// frontal landmarks laid out inside each box and scripted embeddings
+pub struct Fake {
+    pub detections: Vec<Detection>,
+    // embedding of the face at the same index in `detections`,
+    // faces without one get an embedding derived from their box
+    pub embeddings: Vec<Vec<f64>>,
+    pub model: String,
+}
+
+impl Fake {
+    pub fn new(detections: Vec<Detection>, embeddings: Vec<Vec<f64>>) -> Self {
+        Self {
+            detections: detections,
+            embeddings: embeddings,
+            model: String::from("fake@1"),
+        }
+    }
+
+    pub fn with_model(self, model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            ..self
+        }
+    }
+}
+
+impl super::Detector for Fake {
+    fn name(&self) -> String {
+        String::from("fake")
+    }
+
+    fn detect(&self, _image: &DynamicImage) -> Vec<Detection> {
+        self.detections.clone()
+    }
+}
+
+impl super::LandmarkPredictor for Fake {
+    fn landmarks(&self, _image: &DynamicImage, locations: &[Rectangle]) -> Vec<Vec<(i64, i64)>> {
+        locations.iter().map(frontal_landmarks).collect()
+    }
+}
+
+impl super::Encoder for Fake {
+    fn model(&self) -> String {
+        self.model.clone()
+    }
+
+    fn encode(
+        &self,
+        _image: &DynamicImage,
+        faces: &[(Rectangle, Vec<(i64, i64)>)],
+        _jitters: u32,
+    ) -> Vec<Vec<f64>> {
+        faces
+            .iter()
+            .map(|(location, _points)| {
+                let index = self
+                    .detections
+                    .iter()
+                    .position(|detection| &detection.location == location);
+
+                match index.and_then(|index| self.embeddings.get(index)) {
+                    Some(embedding) => embedding.clone(),
+                    None => derived_embedding(location),
+                }
+            })
+            .collect()
+    }
+}
+
+// 68 points of a frontal face scaled into the box
+pub fn frontal_landmarks(location: &Rectangle) -> Vec<(i64, i64)> {
+    let width = (location.right - location.left) as f64;
+    let height = (location.bottom - location.top) as f64;
+    let point = |x: f64, y: f64| {
+        (
+            location.left + (x * width).round() as i64,
+            location.top + (y * height).round() as i64,
+        )
+    };
+
+    let mut landmarks = vec![point(0.5, 0.5); 68];
+    for i in 0..17 {
+        // jaw from the right ear (image left) over the chin to the left ear
+        let angle = std::f64::consts::PI * i as f64 / 16.0;
+        landmarks[i] = point(0.5 - 0.5 * angle.cos(), 0.5 + 0.5 * angle.sin());
+    }
+    for i in 36..42 {
+        landmarks[i] = point(0.3, 0.3);
+    }
+    for i in 42..48 {
+        landmarks[i] = point(0.7, 0.3);
+    }
+    landmarks[30] = point(0.5, 0.3 + 0.45 * 0.7);
+    landmarks
+}
+
+fn derived_embedding(location: &Rectangle) -> Vec<f64> {
+    let seed =
+        (location.left + location.top * 31 + location.right * 961 + location.bottom * 29791) as f64;
+    (0..EMBEDDING_SIZE)
+        .map(|i| ((seed + i as f64) * 0.618).sin() * 0.1)
+        .collect()
+}
@@ -1,11 +1,20 @@
+use crate::detection::quality::Quality;
 use crate::detection::Rectangle;
+use serde::{Serialize, Serializer};
 use std::fmt;
 use uuid::Uuid;
 
+#[derive(Serialize)]
 pub struct FaceImage {
     pub uuid: String,
+    #[serde(rename = "box", serialize_with = "serialize_location")]
     pub face_location: Option<Rectangle>,
+    pub confidence: Option<f64>,
     pub measurements: Vec<f64>,
+    // model that produced measurements
+    pub encoder: String,
+    pub landmarks: Vec<(i64, i64)>,
+    pub quality: Option<Quality>,
 }
 
 impl FaceImage {
@@ -13,7 +22,11 @@ impl FaceImage {
         FaceImage {
             uuid: Uuid::new_v4().to_string(),
             face_location: None,
+            confidence: None,
             measurements: Vec::new(),
+            encoder: String::from(""),
+            landmarks: Vec::new(),
+            quality: None,
         }
     }
 
@@ -21,15 +34,58 @@ impl FaceImage {
         self.face_location = Some(location);
     }
 
+    pub fn store_confidence(&mut self, confidence: Option<f64>) {
+        self.confidence = confidence;
+    }
+
     pub fn store_measurements(&mut self, measurements: Vec<f64>) {
         self.measurements = measurements;
     }
+
+    pub fn store_encoder(&mut self, encoder: String) {
+        self.encoder = encoder;
+    }
+
+    pub fn store_landmarks(&mut self, landmarks: Vec<(i64, i64)>) {
+        self.landmarks = landmarks;
+    }
+
+    pub fn store_quality(&mut self, quality: Quality) {
+        self.quality = Some(quality);
+    }
+}
+
+// dlib rectangle is not serializable
+#[derive(Serialize)]
+struct Location {
+    left: i64,
+    top: i64,
+    right: i64,
+    bottom: i64,
+}
+
+pub fn serialize_location<S: Serializer>(
+    location: &Option<Rectangle>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    location
+        .map(|location| Location {
+            left: location.left,
+            top: location.top,
+            right: location.right,
+            bottom: location.bottom,
+        })
+        .serialize(serializer)
 }
 
 impl fmt::Display for FaceImage {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "face: {}\r\n", self.uuid).unwrap();
         write!(f, "location: {:?}\r\n", self.face_location).unwrap();
+        write!(f, "confidence: {:?}\r\n", self.confidence).unwrap();
+        write!(f, "landmarks: {:?}\r\n", self.landmarks).unwrap();
+        write!(f, "quality: {:?}\r\n", self.quality).unwrap();
+        write!(f, "encoder: {}\r\n", self.encoder).unwrap();
         write!(f, "measurements: {:?}\r\n", self.measurements)
     }
 }
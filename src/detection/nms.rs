@@ -0,0 +1,56 @@
+use crate::detection::{Detection, Rectangle};
+use std::cmp::Ordering;
+
+// intersection over union of two boxes, 0..1
+pub fn iou(a: &Rectangle, b: &Rectangle) -> f64 {
+    let width = (a.right.min(b.right) - a.left.max(b.left)).max(0);
+    let height = (a.bottom.min(b.bottom) - a.top.max(b.top)).max(0);
+    let intersection = (width * height) as f64;
+    let union = (area(a) + area(b)) as f64 - intersection;
+
+    if union <= 0.0 {
+        0.0
+    } else {
+        intersection / union
+    }
+}
+
+// drops detections overlapping a better one by more than `threshold` IoU.
+// Scored detections beat unscored ones, then higher confidence wins, then larger box.
+// Kept detections stay in their original order.
+pub fn suppress(detections: Vec<Detection>, threshold: f64) -> Vec<Detection> {
+    let mut order: Vec<usize> = (0..detections.len()).collect();
+    order.sort_by(|&a, &b| compare(&detections[b], &detections[a]));
+
+    let mut kept: Vec<usize> = Vec::new();
+    for index in order {
+        let overlaps = kept.iter().any(|&other| {
+            iou(&detections[index].location, &detections[other].location) > threshold
+        });
+        if !overlaps {
+            kept.push(index);
+        }
+    }
+
+    detections
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| kept.contains(index))
+        .map(|(_, detection)| detection)
+        .collect()
+}
+
+fn compare(a: &Detection, b: &Detection) -> Ordering {
+    let by_confidence = match (a.confidence, b.confidence) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    };
+
+    by_confidence.then(area(&a.location).cmp(&area(&b.location)))
+}
+
+fn area(rect: &Rectangle) -> i64 {
+    (rect.right - rect.left).max(0) * (rect.bottom - rect.top).max(0)
+}
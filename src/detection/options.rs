@@ -0,0 +1,104 @@
+use crate::detection::backend::Backend;
+use dotenvy::dotenv;
+use std::env;
+use std::str::FromStr;
+
+pub const DEFAULT_CROP_MARGIN: f64 = 0.0;
+pub const DEFAULT_MIN_FACE_SIZE: u32 = 40;
+pub const DEFAULT_NMS_THRESHOLD: f64 = 0.5;
+pub const DEFAULT_JITTERS: u32 = 0;
+// reference faces are measured once, so they can afford a slower, more robust encoding
+pub const ENROLMENT_JITTERS: u32 = 10;
+pub const REALTIME_JITTERS: u32 = 0;
+
+pub struct Options {
+    // context added around each face crop, in percent of the face box size
+    pub crop_margin: f64,
+    // faces with a smaller side (in pixels) are dropped
+    pub min_face_size: u32,
+    // detections scored below are dropped, unscored detections are kept
+    pub min_confidence: Option<f64>,
+    // of overlapping detections (IoU above) only the best one is kept
+    pub nms_threshold: f64,
+    // how many times each face is randomly jittered and re-encoded, encodings are averaged
+    pub jitters: u32,
+    pub backend: Backend,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            crop_margin: DEFAULT_CROP_MARGIN,
+            min_face_size: DEFAULT_MIN_FACE_SIZE,
+            min_confidence: None,
+            nms_threshold: DEFAULT_NMS_THRESHOLD,
+            jitters: DEFAULT_JITTERS,
+            backend: Backend::default(),
+        }
+    }
+}
+
+impl Options {
+    pub fn from_env() -> Self {
+        dotenv().ok();
+        let defaults = Self::default();
+
+        Self {
+            crop_margin: env_var("DETECTION_CROP_MARGIN", defaults.crop_margin),
+            min_face_size: env_var("DETECTION_MIN_FACE_SIZE", defaults.min_face_size),
+            min_confidence: optional_env_var("DETECTION_MIN_CONFIDENCE")
+                .or(defaults.min_confidence),
+            nms_threshold: env_var("DETECTION_NMS_THRESHOLD", defaults.nms_threshold),
+            jitters: env_var("DETECTION_JITTERS", defaults.jitters),
+            backend: defaults.backend,
+        }
+    }
+
+    // everything that affects detection results, as JSON
+    pub fn parameters(&self) -> String {
+        serde_json::json!({
+            "crop_margin": self.crop_margin,
+            "min_face_size": self.min_face_size,
+            "min_confidence": self.min_confidence,
+            "nms_threshold": self.nms_threshold,
+            "jitters": self.jitters,
+        })
+        .to_string()
+    }
+
+    // for faces that become references: directory and cli trainers
+    pub fn enrolment() -> Self {
+        Self {
+            jitters: env_var("DETECTION_ENROLMENT_JITTERS", ENROLMENT_JITTERS),
+            ..Self::from_env()
+        }
+    }
+
+    // for faces already stored: reprocessing, re-encoding and faces drawn by hand.
+    // Library faces are references, so they are measured like enrolled ones
+    pub fn library() -> Self {
+        Self::enrolment()
+    }
+
+    // for the live path: video frames
+    pub fn realtime() -> Self {
+        Self {
+            jitters: env_var("DETECTION_REALTIME_JITTERS", REALTIME_JITTERS),
+            ..Self::from_env()
+        }
+    }
+}
+
+pub fn env_var<T: FromStr>(name: &str, default: T) -> T {
+    optional_env_var(name).unwrap_or(default)
+}
+
+pub fn optional_env_var<T: FromStr>(name: &str) -> Option<T> {
+    match env::var(name) {
+        Err(_) => None,
+        Ok(value) => match value.parse::<T>() {
+            Err(_) => panic!("{} has invalid value: {}", name, value),
+            Ok(parsed) => Some(parsed),
+        },
+    }
+}
@@ -0,0 +1,50 @@
+use image::DynamicImage;
+use std::fs::File;
+use std::io::BufReader;
+
+pub const NORMAL: u32 = 1;
+
+// EXIF Orientation tag value, `NORMAL` when the tag is missing or broken
+pub fn read(path: &String) -> u32 {
+    let Ok(file) = File::open(path) else {
+        return NORMAL;
+    };
+    let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(&file)) else {
+        return NORMAL;
+    };
+
+    match exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY) {
+        Some(field) => match field.value.get_uint(0) {
+            Some(value @ 1..=8) => value,
+            _ => NORMAL,
+        },
+        None => NORMAL,
+    }
+}
+
+// turns the image upright according to the EXIF Orientation value
+pub fn apply(image: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+pub fn describe(orientation: u32) -> &'static str {
+    match orientation {
+        2 => "flip horizontal",
+        3 => "rotate 180",
+        4 => "flip vertical",
+        5 => "rotate 90, flip horizontal",
+        6 => "rotate 90",
+        7 => "rotate 270, flip horizontal",
+        8 => "rotate 270",
+        _ => "none",
+    }
+}
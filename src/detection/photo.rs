@@ -1,13 +1,26 @@
 use crate::detection::face_image::FaceImage;
-use debug_print::debug_println;
-use image::DynamicImage;
+use crate::detection::status::Status;
+use image::{DynamicImage, ImageFormat};
+use serde::{Serialize, Serializer};
 use std::fmt;
 use uuid::Uuid;
 
+#[derive(Serialize)]
 pub struct Photo {
     pub uuid: String,
     pub face_detected: bool,
+    // EXIF orientation that was applied to turn the image upright
+    pub orientation: u32,
+    // format of the stored original
+    #[serde(serialize_with = "serialize_format")]
+    pub format: ImageFormat,
     pub faces: Vec<FaceImage>,
+    pub detector: String,
+    // detection options as JSON
+    pub parameters: String,
+    // seconds spent on detection and encoding
+    pub duration: f64,
+    #[serde(skip)]
     pub image: Option<DynamicImage>,
 }
 
@@ -17,6 +30,11 @@ impl Photo {
             uuid: Uuid::new_v4().to_string(),
             faces: Vec::new(),
             face_detected: false,
+            orientation: crate::detection::orientation::NORMAL,
+            format: ImageFormat::Jpeg,
+            detector: String::from(""),
+            parameters: String::from(""),
+            duration: 0.0,
             image: None,
         }
     }
@@ -26,18 +44,63 @@ impl Photo {
         self.face_detected = true;
     }
 
+    pub fn store_orientation(&mut self, orientation: u32) {
+        self.orientation = orientation;
+    }
+
+    pub fn store_format(&mut self, format: ImageFormat) {
+        self.format = format;
+    }
+
+    pub fn store_run(&mut self, detector: String, parameters: String, duration: f64) {
+        self.detector = detector;
+        self.parameters = parameters;
+        self.duration = duration;
+    }
+
+    pub fn status(&self) -> Status {
+        match self.face_detected {
+            true => Status::Ok,
+            false => Status::NoFaces,
+        }
+    }
+
+    // the face a photo is most likely taken of, when one face stands for the whole photo
+    pub fn largest_face(&self) -> Option<&FaceImage> {
+        self.faces.iter().max_by_key(|face| {
+            face.face_location
+                .map_or(0, |location| crate::detection::face_size(&location))
+        })
+    }
+
     pub fn push_img(&mut self, image: DynamicImage) {
         self.image = Some(image);
     }
 }
 
+fn serialize_format<S: Serializer>(format: &ImageFormat, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(crate::media::name(*format))
+}
+
 impl fmt::Display for Photo {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "#################################\r\n").unwrap();
         write!(f, "{}\r\n", self.uuid).unwrap();
         write!(f, "face detected: {}\r\n", self.face_detected).unwrap();
+        write!(
+            f,
+            "orientation: {}\r\n",
+            crate::detection::orientation::describe(self.orientation)
+        )
+        .unwrap();
+        write!(
+            f,
+            "detector: {} {} in {:.2}s\r\n",
+            self.detector, self.parameters, self.duration
+        )
+        .unwrap();
         for face in self.faces.iter() {
-            debug_println!("{}", face);
+            write!(f, "{}", face).unwrap();
         }
         write!(f, "#################################")
     }
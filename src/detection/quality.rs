@@ -0,0 +1,183 @@
+use dlib_face_recognition::Rectangle;
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage};
+use serde::Serialize;
+
+// indexes in the 68-point (iBUG 300-W) landmarks layout
+const JAW_RIGHT: usize = 0;
+const JAW_LEFT: usize = 16;
+const CHIN: usize = 8;
+const NOSE_TIP: usize = 30;
+const RIGHT_EYE: std::ops::Range<usize> = 36..42;
+const LEFT_EYE: std::ops::Range<usize> = 42..48;
+
+// nose tip sits at about this fraction of the eyes-to-chin height on a frontal face
+const FRONTAL_NOSE_RATIO: f64 = 0.45;
+// blur is measured on the face crop scaled to this size, so faces are comparable
+const BLUR_SAMPLE_SIZE: u32 = 128;
+
+// values at which the corresponding part of the score saturates
+const MAX_YAW: f64 = 60.0;
+const MAX_PITCH: f64 = 60.0;
+const SHARP_BLUR: f64 = 100.0;
+const GOOD_INTER_OCULAR_DISTANCE: f64 = 40.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Quality {
+    // head pose in degrees, rough estimates from landmarks geometry
+    pub yaw: f64,
+    pub pitch: f64,
+    pub roll: f64,
+    // variance of laplacian of the face crop, higher is sharper
+    pub blur: f64,
+    pub inter_ocular_distance: f64,
+    // mean luma of the face crop, 0..255
+    pub brightness: f64,
+    // 0..1, combination of all of the above
+    pub score: f64,
+}
+
+pub fn assess(image: &DynamicImage, location: &Rectangle, landmarks: &Vec<(i64, i64)>) -> Quality {
+    let face = crop_gray(image, location);
+    let (yaw, pitch, roll, inter_ocular_distance) = head_pose(landmarks);
+    let blur = if face.width() == 0 || face.height() == 0 {
+        0.0
+    } else {
+        laplacian_variance(&image::imageops::resize(
+            &face,
+            BLUR_SAMPLE_SIZE,
+            BLUR_SAMPLE_SIZE,
+            FilterType::Triangle,
+        ))
+    };
+    let brightness = mean(&face);
+
+    Quality {
+        yaw: yaw,
+        pitch: pitch,
+        roll: roll,
+        blur: blur,
+        inter_ocular_distance: inter_ocular_distance,
+        brightness: brightness,
+        score: score(yaw, pitch, blur, inter_ocular_distance, brightness),
+    }
+}
+
+pub fn score(yaw: f64, pitch: f64, blur: f64, inter_ocular_distance: f64, brightness: f64) -> f64 {
+    let pose = (1.0 - (yaw.abs() / MAX_YAW).min(1.0)) * (1.0 - (pitch.abs() / MAX_PITCH).min(1.0));
+    let sharpness = (blur / SHARP_BLUR).min(1.0);
+    let size = (inter_ocular_distance / GOOD_INTER_OCULAR_DISTANCE).min(1.0);
+    let exposure = 1.0 - ((brightness - 128.0).abs() / 128.0).min(1.0);
+
+    pose * sharpness * size * exposure
+}
+
+// (yaw, pitch, roll, inter ocular distance)
+pub fn head_pose(landmarks: &Vec<(i64, i64)>) -> (f64, f64, f64, f64) {
+    if landmarks.len() != 68 {
+        return (0.0, 0.0, 0.0, 0.0);
+    }
+
+    let right_eye = center(&landmarks[RIGHT_EYE]);
+    let left_eye = center(&landmarks[LEFT_EYE]);
+    let eyes = (
+        (right_eye.0 + left_eye.0) / 2.0,
+        (right_eye.1 + left_eye.1) / 2.0,
+    );
+    let inter_ocular_distance = distance(right_eye, left_eye);
+
+    let roll_radians = (left_eye.1 - right_eye.1).atan2(left_eye.0 - right_eye.0);
+
+    // measure yaw and pitch in the coordinates of the face with roll removed
+    let upright = |point: (i64, i64)| -> (f64, f64) {
+        let (x, y) = (point.0 as f64 - eyes.0, point.1 as f64 - eyes.1);
+        let (sin, cos) = (-roll_radians).sin_cos();
+        (x * cos - y * sin, x * sin + y * cos)
+    };
+    let nose = upright(landmarks[NOSE_TIP]);
+    let chin = upright(landmarks[CHIN]);
+    let jaw_right = upright(landmarks[JAW_RIGHT]);
+    let jaw_left = upright(landmarks[JAW_LEFT]);
+
+    let to_right = nose.0 - jaw_right.0;
+    let to_left = jaw_left.0 - nose.0;
+    let yaw = if to_right + to_left > 0.0 {
+        ((to_left - to_right) / (to_left + to_right))
+            .clamp(-1.0, 1.0)
+            .asin()
+    } else {
+        0.0
+    };
+
+    let pitch = if chin.1 > 0.0 {
+        ((nose.1 / chin.1 - FRONTAL_NOSE_RATIO) / FRONTAL_NOSE_RATIO)
+            .clamp(-1.0, 1.0)
+            .asin()
+    } else {
+        0.0
+    };
+
+    (
+        yaw.to_degrees(),
+        pitch.to_degrees(),
+        roll_radians.to_degrees(),
+        inter_ocular_distance,
+    )
+}
+
+pub fn laplacian_variance(image: &GrayImage) -> f64 {
+    let (width, height) = image.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let luma = |x: u32, y: u32| image.get_pixel(x, y).0[0] as f64;
+    let mut values = Vec::with_capacity(((width - 2) * (height - 2)) as usize);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            values.push(
+                luma(x - 1, y) + luma(x + 1, y) + luma(x, y - 1) + luma(x, y + 1)
+                    - 4.0 * luma(x, y),
+            );
+        }
+    }
+
+    let average = values.iter().sum::<f64>() / values.len() as f64;
+    values
+        .iter()
+        .map(|value| (value - average).powi(2))
+        .sum::<f64>()
+        / values.len() as f64
+}
+
+fn crop_gray(image: &DynamicImage, location: &Rectangle) -> GrayImage {
+    let area = crate::detection::crop_area(location, 0.0, image.width(), image.height());
+    image
+        .crop_imm(
+            area.left as u32,
+            area.top as u32,
+            (area.right - area.left) as u32,
+            (area.bottom - area.top) as u32,
+        )
+        .to_luma8()
+}
+
+fn mean(image: &GrayImage) -> f64 {
+    let pixels = image.as_raw();
+    if pixels.is_empty() {
+        return 0.0;
+    }
+    pixels.iter().map(|&value| value as f64).sum::<f64>() / pixels.len() as f64
+}
+
+fn center(points: &[(i64, i64)]) -> (f64, f64) {
+    let count = points.len() as f64;
+    (
+        points.iter().map(|point| point.0 as f64).sum::<f64>() / count,
+        points.iter().map(|point| point.1 as f64).sum::<f64>() / count,
+    )
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
@@ -0,0 +1,26 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    NoFaces,
+    // photo is stored, but detection could not be run on it
+    Error,
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::NoFaces => "no_faces",
+            Self::Error => "error",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        [Self::Ok, Self::NoFaces, Self::Error]
+            .into_iter()
+            .find(|status| status.name() == value)
+    }
+}
@@ -0,0 +1,275 @@
+use colored::Colorize;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::detection;
+use crate::detection::orientation;
+use crate::media;
+use crate::recognition;
+use crate::recognition::verdict::Verdict;
+use crate::trainer::directory_trainer::DirectoryTrainer;
+
+// thresholds of the ROC curve go from 0 to ROC_MAX_THRESHOLD by ROC_STEP
+pub const ROC_STEP: f64 = 0.01;
+pub const ROC_MAX_THRESHOLD: f64 = 1.0;
+
+// outcome of recognizing one labelled face
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Probe {
+    pub path: String,
+    // label from the directory name
+    pub name: String,
+    // person of a known verdict
+    pub predicted: Option<String>,
+    // distance to the closest face of the labelled person, none if the person is not enrolled
+    pub genuine: Option<f64>,
+    // distances to the closest faces of every other enrolled person
+    pub impostors: Vec<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RocPoint {
+    pub threshold: f64,
+    // share of impostor distances accepted
+    pub far: f64,
+    // share of genuine distances rejected
+    pub frr: f64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Report {
+    // faces recognized
+    pub probes: usize,
+    // files without a readable face
+    pub skipped: usize,
+    // global threshold rates are reported at
+    pub threshold: f64,
+    // share of faces given the right person, or unknown when the person is not enrolled
+    pub accuracy: f64,
+    pub precision: f64,
+    pub recall: f64,
+    pub far: f64,
+    pub frr: f64,
+    // threshold with the fewest errors, FAR + FRR
+    pub recommended_threshold: f64,
+    pub roc: Vec<RocPoint>,
+    pub results: Vec<Probe>,
+}
+
+pub fn call(dir: &String) -> Report {
+    call_with_options(
+        dir,
+        &detection::options::Options::from_env(),
+        &recognition::options::Options::from_env(),
+    )
+}
+
+// held-out evaluation: the largest face of every photo of the labelled `dir` is recognized
+// against enrolled persons, nothing is stored. Enrolled faces are not cross-validated,
+// so `dir` should be kept out of training
+pub fn call_with_options(
+    dir: &String,
+    detection_options: &detection::options::Options,
+    recognition_options: &recognition::options::Options,
+) -> Report {
+    let mut probes = Vec::new();
+    let mut skipped = 0;
+
+    for file_path in DirectoryTrainer::files_list(Path::new(dir)).iter() {
+        println!("{}", file_path.yellow());
+
+        match probe(file_path, detection_options, recognition_options) {
+            Err(error) => {
+                println!("{} {}", "Skipped:".red(), error);
+                skipped += 1;
+            }
+            Ok(result) => probes.push(result),
+        }
+    }
+
+    let mut result = report(probes, recognition_options.max_distance);
+    result.skipped = skipped;
+    result
+}
+
+fn probe(
+    file_path: &String,
+    detection_options: &detection::options::Options,
+    recognition_options: &recognition::options::Options,
+) -> Result<Probe, Box<dyn Error>> {
+    if !matches!(media::sniff(file_path), media::Kind::Image(_)) {
+        return Err("not a still image".into());
+    }
+
+    let image = orientation::apply(media::open(file_path)?, orientation::read(file_path));
+    let photo = detection::analyze(image, detection_options);
+    let Some(face) = photo.largest_face() else {
+        return Err("no face found".into());
+    };
+
+    let name = DirectoryTrainer::parse_name(file_path);
+    let result = recognition::find_matches_for_measurements(
+        &face.measurements,
+        &face.encoder,
+        face.quality.map(|quality| quality.score),
+        recognition_options,
+    );
+
+    // distances are taken from the index, candidates are already filtered by the strategy
+    let (genuine, impostors): (Vec<_>, Vec<_>) =
+        recognition::person_distances(&face.measurements, &face.encoder)
+            .into_iter()
+            .partition(|(person, _)| person.name == name);
+
+    Ok(Probe {
+        path: file_path.clone(),
+        name: name,
+        predicted: match &result.verdict {
+            Verdict::Known(person, _) => Some(person.name.clone()),
+            _ => None,
+        },
+        genuine: genuine
+            .iter()
+            .map(|(_, distance)| *distance)
+            .reduce(f64::min),
+        impostors: impostors.iter().map(|(_, distance)| *distance).collect(),
+    })
+}
+
+pub fn report(probes: Vec<Probe>, threshold: f64) -> Report {
+    let enrolled = probes
+        .iter()
+        .filter(|probe| probe.genuine.is_some())
+        .count();
+    let predicted = probes
+        .iter()
+        .filter(|probe| probe.predicted.is_some())
+        .count();
+    let true_positives = probes
+        .iter()
+        .filter(|probe| probe.predicted.as_ref() == Some(&probe.name))
+        .count();
+    let true_negatives = probes
+        .iter()
+        .filter(|probe| probe.genuine.is_none() && probe.predicted.is_none())
+        .count();
+
+    let steps = (ROC_MAX_THRESHOLD / ROC_STEP).round() as usize;
+    let roc: Vec<RocPoint> = (0..=steps)
+        .map(|step| roc_point(&probes, ROC_MAX_THRESHOLD * step as f64 / steps as f64))
+        .collect();
+    let recommended_threshold = roc
+        .iter()
+        .min_by(|a, b| (a.far + a.frr).total_cmp(&(b.far + b.frr)))
+        .map_or(threshold, |point| point.threshold);
+    let rates = roc_point(&probes, threshold);
+
+    Report {
+        probes: probes.len(),
+        skipped: 0,
+        threshold: threshold,
+        accuracy: ratio(true_positives + true_negatives, probes.len()),
+        precision: ratio(true_positives, predicted),
+        recall: ratio(true_positives, enrolled),
+        far: rates.far,
+        frr: rates.frr,
+        recommended_threshold: recommended_threshold,
+        roc: roc,
+        results: probes,
+    }
+}
+
+fn roc_point(probes: &[Probe], threshold: f64) -> RocPoint {
+    let genuine: Vec<f64> = probes.iter().filter_map(|probe| probe.genuine).collect();
+    let impostors: Vec<f64> = probes
+        .iter()
+        .flat_map(|probe| probe.impostors.iter().copied())
+        .collect();
+
+    RocPoint {
+        threshold: threshold,
+        far: ratio(
+            impostors
+                .iter()
+                .filter(|distance| **distance < threshold)
+                .count(),
+            impostors.len(),
+        ),
+        frr: ratio(
+            genuine
+                .iter()
+                .filter(|distance| **distance >= threshold)
+                .count(),
+            genuine.len(),
+        ),
+    }
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    match total {
+        0 => 0.0,
+        _ => count as f64 / total as f64,
+    }
+}
+
+impl Report {
+    // writes `<prefix>.json` with the whole report and `<prefix>_roc.csv` with the ROC curve
+    pub fn save(&self, prefix: &String) -> std::io::Result<()> {
+        if let Some(parent) = Path::new(prefix).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(
+            format!("{}.json", prefix),
+            serde_json::to_string_pretty(self).unwrap(),
+        )?;
+        fs::write(format!("{}_roc.csv", prefix), self.roc_csv())
+    }
+
+    pub fn roc_csv(&self) -> String {
+        let mut csv = String::from("threshold,far,frr\n");
+        for point in self.roc.iter() {
+            csv.push_str(&format!(
+                "{:.2},{:.4},{:.4}\n",
+                point.threshold, point.far, point.frr
+            ));
+        }
+        csv
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Faces evaluated: {} (skipped: {})\r\n",
+            self.probes, self.skipped
+        )
+        .unwrap();
+        write!(
+            f,
+            "Held-out only, enrolled faces are not cross-validated\r\n"
+        )
+        .unwrap();
+        write!(f, "Accuracy: {:.4}\r\n", self.accuracy).unwrap();
+        write!(
+            f,
+            "Precision: {:.4}, recall: {:.4}\r\n",
+            self.precision, self.recall
+        )
+        .unwrap();
+        write!(
+            f,
+            "FAR: {:.4}, FRR: {:.4} at threshold {:.2}\r\n",
+            self.far, self.frr, self.threshold
+        )
+        .unwrap();
+        write!(
+            f,
+            "Recommended threshold: {:.2}",
+            self.recommended_threshold
+        )
+    }
+}
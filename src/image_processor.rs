@@ -1,10 +1,13 @@
 use crate::detection;
+use crate::detection::options::Options;
+use crate::media;
 use crate::recognition;
+use crate::recognition::verdict::Verdict;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::error::Error;
 
-const MAX_DISTANCE: f64 = 0.6;
-
+#[derive(Serialize)]
 pub struct ProcessingResult {
     pub photo: detection::photo::Photo,
     pub display_data: Vec<(String, f64)>,
@@ -18,9 +21,13 @@ impl ProcessingResult {
 }
 
 pub fn call(path: &String) -> Option<ProcessingResult> {
-    match recognize_faces(&path) {
+    call_with_options(path, &Options::from_env())
+}
+
+pub fn call_with_options(path: &String, options: &Options) -> Option<ProcessingResult> {
+    match recognize_faces_with_options(&path, options) {
         Err(error) => {
-            println!("Face detection failed: {:?}", error);
+            eprintln!("Face detection failed: {:?}", error);
             None
         },
         Ok(data) => {
@@ -28,42 +35,111 @@ pub fn call(path: &String) -> Option<ProcessingResult> {
             let mut recognition_results = data;
 
             for (face_uuid, &ref recognition) in recognition_results.face_matches.iter() {
-                if recognition.matches.len() == 0 {
-                    continue;
-                }
+                crate::db::face::Face::update_verdict(
+                    &face_uuid,
+                    &recognition.verdict,
+                    recognition.threshold,
+                );
 
-                let (Some(person), coef) = &recognition.matches[0] else { todo!() };
-
-                if coef < &MAX_DISTANCE {
+                if let Verdict::Known(person, _) = &recognition.verdict {
                     crate::db::face::Face::update_person_uuid(&face_uuid, &person.uuid);
-                    result.push((person.name.clone(), *coef));
+                    let distance = recognition
+                        .candidates
+                        .iter()
+                        .find(|candidate| &candidate.person == person)
+                        .map_or(0.0, |candidate| candidate.distance);
+                    result.push((person.name.clone(), distance));
                 }
             }
-            println!("{:?}", result);
             recognition_results.display_data = result;
             Some(recognition_results)
         },
     }
 }
 
+// adds a face the detector missed, `location` is in pixels of the stored original
+pub fn annotate_face(
+    photo_uuid: &String,
+    location: dlib_face_recognition::Rectangle,
+) -> Result<crate::db::face::Face, Box<dyn Error>> {
+    annotate_face_with_options(photo_uuid, location, &Options::library())
+}
+
+// the face is marked as annotated, so reprocessing keeps it, and counted in the photo
+pub fn annotate_face_with_options(
+    photo_uuid: &String,
+    location: dlib_face_recognition::Rectangle,
+    options: &Options,
+) -> Result<crate::db::face::Face, Box<dyn Error>> {
+    let photo = crate::db::photo::Photo::find(photo_uuid);
+    let face = detection::annotate(&photo.uuid, &photo.original_file_name(), location, options)?;
+
+    crate::db::face::Face::create(&photo.uuid, &face);
+    crate::db::face::Face::update_annotated(&face.uuid);
+    crate::db::photo::Photo::update_face_count(&photo.uuid);
+    Ok(crate::db::face::Face::find(&face.uuid))
+}
+
 pub fn recognize_faces(path: &String) -> Result<ProcessingResult, Box<dyn Error>> {
-    match detection::call(&path) {
-        Err(error) => Err(error),
-        Ok(photo) => {
-            let mut recognition_results = HashMap::new();
-            crate::db::photo::Photo::save_detection_result(&photo);
+    recognize_faces_with_options(path, &Options::from_env())
+}
 
-            for face in photo.faces.iter() {
-                let result = recognition::find_matches(&face.uuid);
+pub fn recognize_faces_with_options(
+    path: &String,
+    options: &Options,
+) -> Result<ProcessingResult, Box<dyn Error>> {
+    if media::sniff(path) == media::Kind::AnimatedGif {
+        return recognize_animation(path, options);
+    }
 
-                recognition_results.insert(face.uuid.clone(), result);
-            }
+    Ok(recognize_photo(detection::call_with_options(&path, options)?))
+}
 
-            Ok(ProcessingResult {
-                photo: photo,
-                face_matches: recognition_results,
-                display_data: Vec::new(),
-            })        
-        },
+fn recognize_photo(photo: detection::photo::Photo) -> ProcessingResult {
+    let mut recognition_results = HashMap::new();
+    crate::db::photo::Photo::save_detection_result(&photo);
+
+    let recognition_options = recognition::options::Options::from_env();
+    if let Some(source) = &recognition_options.source {
+        crate::db::photo::Photo::update(
+            &photo.uuid,
+            vec![(&"source".to_string(), source)],
+        );
+    }
+
+    for face in photo.faces.iter() {
+        let result = recognition::find_matches_with_options(&face.uuid, &recognition_options);
+
+        recognition_results.insert(face.uuid.clone(), result);
+    }
+
+    ProcessingResult {
+        photo: photo,
+        face_matches: recognition_results,
+        display_data: Vec::new(),
+    }
+}
+
+// first frame with a face, or the last frame when there are none.
+// Frames are only analyzed, the chosen one is stored
+fn recognize_animation(
+    path: &String,
+    options: &Options,
+) -> Result<ProcessingResult, Box<dyn Error>> {
+    let mut chosen = None;
+    crate::video_processor::each_frame(path, |path_to_frame| {
+        let Ok(image) = media::open(path_to_frame) else { return false };
+        let photo = detection::analyze(image, options);
+        let found = !photo.faces.is_empty();
+        chosen = Some(photo);
+        found
+    })?;
+
+    match chosen {
+        None => Err(format!("No frames decoded: {}", path).into()),
+        Some(mut photo) => {
+            detection::store_analyzed(&mut photo, options);
+            Ok(recognize_photo(photo))
+        }
     }
 }
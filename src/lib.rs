@@ -1,8 +1,16 @@
+#![recursion_limit = "1024"]
+
+pub mod clusterer;
 pub mod db;
 pub mod detection;
+pub mod evaluator;
 pub mod image_processor;
+pub mod media;
 pub mod video_processor;
 pub mod recognition;
+pub mod redactor;
+pub mod reencoder;
+pub mod reprocessor;
 pub mod storage;
 pub mod trainer;
 pub mod ui;
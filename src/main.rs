@@ -2,21 +2,49 @@
 // // camera integration
 
 use colored::Colorize;
-use debug_print::debug_println;
+use debug_print::debug_eprintln;
 use spinners::{Spinner, Spinners};
 use std::time::Instant;
+use uuid::Uuid;
 
 use std::env;
+use std::path::Path;
+use video_sentry::clusterer;
 use video_sentry::db;
+use video_sentry::evaluator;
 
 use video_sentry::image_processor;
+use video_sentry::recognition;
+use video_sentry::redactor;
+use video_sentry::reencoder;
+use video_sentry::reprocessor;
 use video_sentry::trainer;
 use video_sentry::ui;
 use video_sentry::video_processor;
 
+const USAGE: &str = "Usage: cargo run <command>, commands:
+  ui
+  cli_trainer
+  train path_to_dir
+  processor path_to_photo [--json]
+  video path_to_video
+  redact input_path output_path [all|unknown|except=name_1,name_2] [blur|pixelate]
+  reprocess [all|needed|photo_uuid_1,photo_uuid_2]
+  reencode
+  evaluate path_to_dir [output_prefix]
+  cluster
+  clusters
+  assign_cluster cluster_uuid name
+  duplicates
+  merge person_uuid other_person_uuid
+  search face_uuid|path_to_image [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--source name] [--json]
+  verify path_to_image_1 path_to_image_2 | face_uuid_1 face_uuid_2 [--json]
+  outliers
+  threshold person|source name distance|reset";
+
 fn main() {
     let args: Vec<String> = env::args().collect();
-    debug_println!("{:?}", args);
+    debug_eprintln!("{:?}", args);
 
     db::init();
 
@@ -24,18 +52,179 @@ fn main() {
         match args[1].as_str() {
             "ui" => ui::ui().unwrap(),
             "cli_trainer" => trainer::cli::cli(),
-            "train" => stdout_wrapper(|| {
-                trainer::directory_trainer::DirectoryTrainer::new(args[2].clone()).call()
+            "train" => {
+                let dir = required(&args, 2, "train path_to_dir");
+                stdout_wrapper(|| trainer::directory_trainer::DirectoryTrainer::new(dir.clone()).call())
+            }
+            "processor" if args.iter().any(|arg| arg == "--json") => {
+                let path = required(&args, 2, "processor path_to_photo [--json]");
+                if let Some(result) = image_processor::call(path) {
+                    println!("{}", serde_json::to_string_pretty(&result).unwrap());
+                }
+            }
+            "processor" => {
+                let path = required(&args, 2, "processor path_to_photo [--json]");
+                stdout_wrapper(|| {
+                    if let Some(result) = image_processor::call(path) {
+                        for (face_uuid, recognition) in result.face_matches.iter() {
+                            println!("{} {}", face_uuid, recognition.verdict);
+                        }
+                        println!("{:?}", result.display_data);
+                    }
+                })
+            }
+            "video" => {
+                let path = required(&args, 2, "video path_to_video");
+                stdout_wrapper(|| {
+                    video_processor::call(path).unwrap();
+                })
+            }
+            "redact" => {
+                let usage = "redact input_path output_path [all|unknown|except=name_1,name_2] [blur|pixelate]";
+                let input_path = required(&args, 2, usage);
+                let output_path = required(&args, 3, usage);
+                let target = redactor::Target::parse(args.get(4).map_or("all", |arg| arg.as_str()))
+                    .unwrap_or_else(|error| fail(&error.to_string()));
+                let style = redactor::Style::parse(args.get(5).map_or("blur", |arg| arg.as_str()))
+                    .unwrap_or_else(|error| fail(&error.to_string()));
+                stdout_wrapper(|| {
+                    let count = redactor::call(input_path, output_path, &target, &style)
+                        .unwrap_or_else(|error| fail(&error.to_string()));
+                    println!("Faces redacted: {}", count);
+                })
+            }
+            "reprocess" => stdout_wrapper(|| {
+                let selection =
+                    reprocessor::Selection::parse(args.get(2).map_or("all", |arg| arg.as_str()));
+                println!("{}", reprocessor::call(&selection));
             }),
-            "processor" => stdout_wrapper(|| {
-                image_processor::call(&args[2]);
+            "reencode" => stdout_wrapper(|| {
+                println!("{}", reencoder::call());
             }),
-            "video" => stdout_wrapper(|| {
-                video_processor::call(&args[2]).unwrap();
+            "cluster" => stdout_wrapper(|| {
+                println!("{}", clusterer::call());
             }),
-            &_ => todo!(),
+            "clusters" => {
+                for cluster in db::cluster::Cluster::all() {
+                    println!("{} {}", cluster.uuid, cluster.face_count);
+                }
+            }
+            "assign_cluster" => {
+                let cluster_uuid = required(&args, 2, "assign_cluster cluster_uuid name");
+                let name = required(&args, 3, "assign_cluster cluster_uuid name");
+                let (person, assigned) = clusterer::assign(cluster_uuid, name);
+                println!("Faces assigned to {}: {}", person.name, assigned);
+            }
+            "evaluate" => {
+                let report = evaluator::call(required(&args, 2, "evaluate path_to_dir [output_prefix]"));
+                let prefix = args.get(3).map_or("evaluation".to_string(), |arg| arg.clone());
+                report.save(&prefix).unwrap();
+                println!("{}", report);
+            }
+            "duplicates" => {
+                for duplicate in recognition::duplicates::call() {
+                    println!("{}", duplicate);
+                }
+            }
+            "merge" => {
+                let uuid = required(&args, 2, "merge person_uuid other_person_uuid");
+                let other_uuid = required(&args, 3, "merge person_uuid other_person_uuid");
+                let moved = db::person::Person::merge(uuid, other_uuid).unwrap_or_else(|error| fail(&error.to_string()));
+                println!("Faces moved: {}", moved);
+            }
+            "search" => {
+                let input = required(
+                    &args,
+                    2,
+                    "search face_uuid|path_to_image [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--source name] [--json]",
+                );
+                let date = |flag: &str| {
+                    flag_value(&args, flag).map(|value| {
+                        recognition::search::parse_date(&value).unwrap_or_else(|error| fail(&error.to_string()))
+                    })
+                };
+                let options = recognition::search::Options {
+                    from: date("--from"),
+                    to: date("--to"),
+                    source: flag_value(&args, "--source"),
+                    ..recognition::search::Options::from_env()
+                };
+                let hits = match is_face_uuid(input) {
+                    true => recognition::search::by_face(input, &options),
+                    false => recognition::search::by_image(input, &options),
+                }
+                .unwrap_or_else(|error| fail(&error.to_string()));
+                if args.iter().any(|arg| arg == "--json") {
+                    println!("{}", serde_json::to_string_pretty(&hits).unwrap());
+                } else {
+                    for hit in hits.iter() {
+                        println!("{}", hit);
+                    }
+                }
+            }
+            "verify" => {
+                let usage = "verify path_to_image_1 path_to_image_2 | face_uuid_1 face_uuid_2 [--json]";
+                let left = required(&args, 2, usage);
+                let right = required(&args, 3, usage);
+                let verification = match (is_face_uuid(left), is_face_uuid(right)) {
+                    (false, false) => recognition::verification::images(left, right),
+                    (true, true) => recognition::verification::faces(left, right),
+                    _ => fail("Verify two images or two stored faces"),
+                }
+                .unwrap_or_else(|error| fail(&error.to_string()));
+                if args.iter().any(|arg| arg == "--json") {
+                    println!("{}", serde_json::to_string_pretty(&verification).unwrap());
+                } else {
+                    println!("{}", verification);
+                }
+            }
+            "outliers" => {
+                for outlier in recognition::outliers::call() {
+                    println!("{}", outlier);
+                }
+            }
+            "threshold" => {
+                let usage = "threshold person|source name distance|reset";
+                let target = required(&args, 2, usage);
+                let name = required(&args, 3, usage);
+                let value = required(&args, 4, usage);
+                recognition::thresholds::set(target, name, value).unwrap_or_else(|error| fail(&error.to_string()));
+            }
+            command => fail(&format!("Unknown command: {}\n{}", command, USAGE)),
         }
+    } else {
+        fail(USAGE);
+    }
+}
+
+// value following `name`, e.g. "--from 2023-06-01"
+fn flag_value(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+// stored faces are given by uuid, new images by path
+fn is_face_uuid(arg: &String) -> bool {
+    if Uuid::parse_str(arg).is_ok() {
+        return true;
     }
+    if !Path::new(arg).is_file() {
+        fail(&format!("Neither a face uuid nor an image file: {}", arg));
+    }
+    false
+}
+
+// argument at `position`, the usage of the command is printed when it's missing
+fn required<'a>(args: &'a [String], position: usize, usage: &str) -> &'a String {
+    args.get(position)
+        .unwrap_or_else(|| fail(&format!("Usage: cargo run {}", usage)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
 }
 
 fn stdout_wrapper<F: Fn()>(f: F) {
@@ -0,0 +1,75 @@
+use image::codecs::gif::GifDecoder;
+use image::io::Reader;
+use image::{AnimationDecoder, DynamicImage, ImageFormat, ImageResult};
+use std::fs::File;
+use std::io::{BufReader, Read};
+
+pub const SUPPORTED_FORMATS: [ImageFormat; 6] = [
+    ImageFormat::Jpeg,
+    ImageFormat::Png,
+    ImageFormat::WebP,
+    ImageFormat::Bmp,
+    ImageFormat::Tiff,
+    ImageFormat::Gif,
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Kind {
+    Image(ImageFormat),
+    // processed as a short video, frame by frame
+    AnimatedGif,
+    Unsupported,
+}
+
+// by file content, extension is ignored
+pub fn sniff(path: &String) -> Kind {
+    let mut header = Vec::new();
+    let read = File::open(path).and_then(|file| file.take(64).read_to_end(&mut header));
+    if read.is_err() {
+        return Kind::Unsupported;
+    }
+
+    match image::guess_format(&header) {
+        Ok(ImageFormat::Gif) if is_animated_gif(path) => Kind::AnimatedGif,
+        Ok(format) if SUPPORTED_FORMATS.contains(&format) => Kind::Image(format),
+        _ => Kind::Unsupported,
+    }
+}
+
+pub fn open(path: &String) -> ImageResult<DynamicImage> {
+    Reader::open(path)?.with_guessed_format()?.decode()
+}
+
+pub fn extension(format: ImageFormat) -> &'static str {
+    format.extensions_str().first().copied().unwrap_or("img")
+}
+
+pub fn name(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Jpeg => "jpeg",
+        ImageFormat::Png => "png",
+        ImageFormat::WebP => "webp",
+        ImageFormat::Bmp => "bmp",
+        ImageFormat::Tiff => "tiff",
+        ImageFormat::Gif => "gif",
+        _ => "unknown",
+    }
+}
+
+pub fn from_name(value: &str) -> Option<ImageFormat> {
+    SUPPORTED_FORMATS
+        .iter()
+        .find(|format| name(**format) == value)
+        .copied()
+}
+
+fn is_animated_gif(path: &String) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    let Ok(decoder) = GifDecoder::new(BufReader::new(file)) else {
+        return false;
+    };
+
+    decoder.into_frames().take(2).count() > 1
+}
@@ -1,15 +1,43 @@
-use debug_print::debug_println;
-use dlib_face_recognition::*;
+use debug_print::debug_eprintln;
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
 use std::fmt;
 
+use crate::db::person::Person;
+
+use crate::recognition::options::Options;
+use crate::recognition::thresholds::Thresholds;
+use crate::recognition::verdict::Verdict;
+
+pub mod duplicates;
+pub mod index;
+pub mod options;
+pub mod outliers;
+pub mod scoring;
+pub mod search;
+pub mod thresholds;
+pub mod verification;
+pub mod verdict;
+
+#[derive(Serialize)]
 pub struct Data {
+    // closest faces
+    #[serde(serialize_with = "serialize_matches")]
     pub matches: Vec<(Option<crate::db::person::Person>, f64)>,
+    // persons ranked by the matching strategy, best first
+    pub candidates: Vec<scoring::Candidate>,
+    pub verdict: Verdict,
+    // threshold the verdict was decided with, none if the face was not matched
+    pub threshold: Option<f64>,
 }
 
 impl Data {
     pub fn new() -> Self {
         Self {
             matches: Vec::new(),
+            candidates: Vec::new(),
+            verdict: Verdict::Unknown,
+            threshold: None,
         }
     }
 
@@ -19,29 +47,105 @@ impl Data {
 }
 
 pub fn find_matches(uuid: &String) -> Data {
+    find_matches_with_options(uuid, &Options::from_env())
+}
+
+pub fn find_matches_with_options(uuid: &String, options: &Options) -> Data {
     let left_face = crate::db::face::Face::find(&uuid);
+
+    find_matches_for_measurements(
+        &left_face.deserialize_measurements(),
+        &left_face.encoder,
+        left_face.quality,
+        options,
+    )
+}
+
+// compares a face that is not necessarily stored in the database,
+// only with faces measured by the same `encoder`
+pub fn find_matches_for_measurements(
+    measurements: &Vec<f64>,
+    encoder: &String,
+    quality: Option<f64>,
+    options: &Options,
+) -> Data {
     let mut result = Data::new();
 
-    let left_encoding = face_encoding(&left_face);
-
-    for right_face in crate::db::face::Face::where_all(&"moderated = 'true'".to_string()) {
-        let right_encoding = face_encoding(&right_face);
-        let distance = left_encoding.distance(&right_encoding);
-        let person = if right_face.person_uuid != "" {
-            Some(crate::db::person::Person::find(&right_face.person_uuid))
-        } else {
-            None
-        };
-        result.matches.push((person, distance));
+    if !is_good_enough(quality, options.min_quality) {
+        debug_eprintln!("Face is skipped due to low quality");
+        return result;
     }
 
-    Data::order_matches(&mut result.matches);
-    debug_println!("{}", result);
+    let thresholds = Thresholds::load(options);
+
+    index::with_index(|index| {
+        let distances = index.distances(measurements, encoder, options.min_quality);
+
+        result.candidates = scoring::rank(
+            &distances,
+            measurements,
+            options.strategy,
+            options.top_k,
+            options.top_n,
+            &thresholds,
+        );
+        result.matches = index::closest(distances, options.top_k)
+            .into_iter()
+            .map(|(entry, distance)| (entry.person.clone(), distance))
+            .collect();
+    });
+    result.verdict = verdict::decide(&result.candidates, options.strategy_min_margin());
+    result.threshold = Some(match &result.verdict {
+        Verdict::Known(person, _) => thresholds.for_person(person),
+        _ => thresholds.default_value(),
+    });
+
+    debug_eprintln!("{}", result);
     result
 }
 
-fn face_encoding(face: &crate::db::face::Face) -> FaceEncoding {
-    FaceEncoding::from_vec(&face.deserialize_measurements()).unwrap()
+// distance to the closest face of every person measured by `encoder`, regardless of the
+// strategy and the quality of faces, so evaluation doesn't depend on how a verdict is decided
+pub fn person_distances(measurements: &[f64], encoder: &String) -> Vec<(Person, f64)> {
+    index::with_index(|index| {
+        let mut closest: HashMap<&String, (&Person, f64)> = HashMap::new();
+        for (entry, face_distance) in index.distances(measurements, encoder, f64::MIN) {
+            let Some(person) = &entry.person else { continue };
+            let best = closest.entry(&person.uuid).or_insert((person, face_distance));
+            if face_distance < best.1 {
+                *best = (person, face_distance);
+            }
+        }
+
+        closest
+            .into_values()
+            .map(|(person, face_distance)| (person.clone(), face_distance))
+            .collect()
+    })
+}
+
+// faces assessed before quality was recorded are trusted
+pub fn is_good_enough(quality: Option<f64>, min_quality: f64) -> bool {
+    match quality {
+        Some(quality) => quality >= min_quality,
+        None => true,
+    }
+}
+
+#[derive(Serialize)]
+struct Match<'a> {
+    person: &'a Option<crate::db::person::Person>,
+    distance: f64,
+}
+
+fn serialize_matches<S: Serializer>(
+    matches: &Vec<(Option<crate::db::person::Person>, f64)>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(matches.iter().map(|(person, distance)| Match {
+        person,
+        distance: *distance,
+    }))
 }
 
 impl fmt::Display for Data {
@@ -49,8 +153,27 @@ impl fmt::Display for Data {
         write!(f, "..................................\r\n").unwrap();
         write!(f, "Face comparison results: \r\n",).unwrap();
         for item in self.matches.iter() {
-            println!("{} -> {:?}", item.1, item.0);
+            write!(f, "{} -> {:?}\r\n", item.1, item.0).unwrap();
+        }
+        write!(f, "Candidates: \r\n",).unwrap();
+        for candidate in self.candidates.iter() {
+            write!(
+                f,
+                "{} -> score {:.3}, distance {:.3}, margin {}\r\n",
+                candidate.person.name,
+                candidate.score,
+                candidate.distance,
+                crate::detection::format_score(candidate.margin)
+            )
+            .unwrap();
         }
+        write!(
+            f,
+            "Verdict: {} (threshold: {})\r\n",
+            self.verdict,
+            crate::detection::format_score(self.threshold)
+        )
+        .unwrap();
         write!(f, "..................................\r\n")
     }
 }
@@ -0,0 +1,140 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::db::person::Person;
+use crate::recognition::index::{self, distance, median, Entry};
+use crate::recognition::options::Options;
+use crate::recognition::thresholds::Thresholds;
+
+// two persons that are likely the same one, `other` can be merged into `person`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Duplicate {
+    pub person: Person,
+    pub other: Person,
+    // median distance between faces of the two persons, none if they have no comparable faces
+    pub distance: Option<f64>,
+    // faces are closer than the thresholds of both persons
+    pub close_faces: bool,
+    pub similar_names: bool,
+}
+
+pub fn call() -> Vec<Duplicate> {
+    let thresholds = Thresholds::load(&Options::from_env());
+    let persons = Person::all();
+    index::with_index(|index| find(&persons, index.entries(), &thresholds))
+}
+
+// pairs of persons with close face sets first, closest first, then pairs with similar names only
+pub fn find(persons: &[Person], entries: &[Entry], thresholds: &Thresholds) -> Vec<Duplicate> {
+    let mut faces_by_person: HashMap<&String, Vec<&Entry>> = HashMap::new();
+    for entry in entries.iter() {
+        if let Some(person) = &entry.person {
+            faces_by_person.entry(&person.uuid).or_default().push(entry);
+        }
+    }
+    let no_faces = Vec::new();
+
+    let mut duplicates = Vec::new();
+    for (i, person) in persons.iter().enumerate() {
+        for other in persons[(i + 1)..].iter() {
+            let faces = faces_by_person.get(&person.uuid).unwrap_or(&no_faces);
+            let other_faces = faces_by_person.get(&other.uuid).unwrap_or(&no_faces);
+
+            let mut distances: Vec<f64> = Vec::new();
+            for face in faces.iter() {
+                for other_face in other_faces.iter().filter(|other_face| other_face.encoder == face.encoder) {
+                    distances.push(distance(&face.measurements, &other_face.measurements));
+                }
+            }
+            let median_distance = median(distances);
+
+            let threshold = thresholds.for_person(person).min(thresholds.for_person(other));
+            let close_faces = median_distance.map_or(false, |median_distance| median_distance < threshold);
+            let similar_names = similar_names(&person.name, &other.name);
+
+            if close_faces || similar_names {
+                duplicates.push(Duplicate {
+                    person: person.clone(),
+                    other: other.clone(),
+                    distance: median_distance,
+                    close_faces: close_faces,
+                    similar_names: similar_names,
+                });
+            }
+        }
+    }
+
+    duplicates.sort_by(|a, b| {
+        b.close_faces
+            .cmp(&a.close_faces)
+            .then(a.distance.unwrap_or(f64::MAX).total_cmp(&b.distance.unwrap_or(f64::MAX)))
+    });
+    duplicates
+}
+
+// names that are equal apart from case and separators, one is a part of the other
+// ("john" and "John Doe") or differ by a typo
+pub fn similar_names(name: &str, other: &str) -> bool {
+    let name = normalize_name(name);
+    let other = normalize_name(other);
+    if name.is_empty() || other.is_empty() {
+        return false;
+    }
+    if name == other {
+        return true;
+    }
+
+    let words: Vec<&str> = name.split(' ').collect();
+    let other_words: Vec<&str> = other.split(' ').collect();
+    let (shorter, longer) = match words.len() <= other_words.len() {
+        true => (&words, &other_words),
+        false => (&other_words, &words),
+    };
+    if shorter.len() < longer.len() && shorter.iter().all(|word| longer.contains(word)) {
+        return true;
+    }
+
+    name.chars().count().min(other.chars().count()) >= 4 && edit_distance(&name, &other) <= 1
+}
+
+fn normalize_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + (a_char != *b_char) as usize;
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl fmt::Display for Duplicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) and {} ({}): faces {}",
+            self.person.name,
+            self.person.uuid,
+            self.other.name,
+            self.other.uuid,
+            crate::detection::format_score(self.distance)
+        )
+        .unwrap();
+        if self.similar_names {
+            write!(f, ", similar names").unwrap();
+        }
+        Ok(())
+    }
+}
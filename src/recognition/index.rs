@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::db::face::Face;
+use crate::db::person::Person;
+use crate::recognition::is_good_enough;
+
+// moderated faces, loaded from the database on the first query and kept in sync
+// by moderation, deletion and re-encoding
+static INDEX: Mutex<Option<Index>> = Mutex::new(None);
+
+// faces of every photo, labelled or not, loaded on the first search and kept in sync
+// by detection, deletion and re-encoding
+static LIBRARY: Mutex<Option<Library>> = Mutex::new(None);
+
+pub struct Entry {
+    pub face_uuid: String,
+    pub person: Option<Person>,
+    pub encoder: String,
+    pub quality: Option<f64>,
+    pub measurements: Vec<f64>,
+}
+
+// faces in a vector, with the position of every face uuid, so faces are found,
+// replaced and removed without a scan
+struct Entries<T> {
+    items: Vec<T>,
+    positions: HashMap<String, usize>,
+}
+
+trait FaceEntry {
+    fn face_uuid(&self) -> &String;
+}
+
+impl<T: FaceEntry> Entries<T> {
+    fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, item: T) {
+        match self.positions.get(item.face_uuid()) {
+            Some(&position) => self.items[position] = item,
+            None => {
+                self.positions
+                    .insert(item.face_uuid().clone(), self.items.len());
+                self.items.push(item);
+            }
+        }
+    }
+
+    // the last face takes the place of the removed one
+    fn remove(&mut self, face_uuid: &String) {
+        let Some(position) = self.positions.remove(face_uuid) else {
+            return;
+        };
+        self.items.swap_remove(position);
+        if let Some(moved) = self.items.get(position) {
+            self.positions.insert(moved.face_uuid().clone(), position);
+        }
+    }
+
+    fn get_mut(&mut self, face_uuid: &String) -> Option<&mut T> {
+        let position = *self.positions.get(face_uuid)?;
+        self.items.get_mut(position)
+    }
+}
+
+impl FaceEntry for Entry {
+    fn face_uuid(&self) -> &String {
+        &self.face_uuid
+    }
+}
+
+// exact nearest neighbours by a linear scan over the embeddings of every face: a few
+// milliseconds for tens of thousands of faces in a release build. An approximate index
+// (HNSW, VP-tree) could return the top-k faster, but every strategy except the k-NN vote
+// scores all faces of each person, so queries would still scan them
+pub struct Index {
+    entries: Entries<Entry>,
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Self {
+            entries: Entries::new(),
+        }
+    }
+}
+
+impl Index {
+    pub fn new(entries: Vec<Entry>) -> Self {
+        let mut index = Self::default();
+        for entry in entries {
+            index.entries.insert(entry);
+        }
+        index
+    }
+
+    pub fn load() -> Self {
+        let persons: HashMap<String, Person> = Person::all()
+            .into_iter()
+            .map(|person| (person.uuid.clone(), person))
+            .collect();
+        let mut index = Self::default();
+
+        for face in Face::where_all(&"moderated = 'true'".to_string()) {
+            let person = persons.get(&face.person_uuid).cloned();
+            index.insert(&face, person);
+        }
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.items.is_empty()
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries.items
+    }
+
+    pub fn insert(&mut self, face: &Face, person: Option<Person>) {
+        self.entries.insert(Entry {
+            face_uuid: face.uuid.clone(),
+            person: person,
+            encoder: face.encoder.clone(),
+            quality: face.quality,
+            measurements: face.deserialize_measurements(),
+        });
+    }
+
+    pub fn remove(&mut self, face_uuid: &String) {
+        self.entries.remove(face_uuid);
+    }
+
+    pub fn update_person(&mut self, person: &Person) {
+        for entry in self.entries.items.iter_mut() {
+            if entry
+                .person
+                .as_ref()
+                .map_or(false, |entry_person| entry_person.uuid == person.uuid)
+            {
+                entry.person = Some(person.clone());
+            }
+        }
+    }
+
+    pub fn reassign_person(&mut self, from_person_uuid: &String, person: &Person) {
+        for entry in self.entries.items.iter_mut() {
+            if entry
+                .person
+                .as_ref()
+                .map_or(false, |entry_person| &entry_person.uuid == from_person_uuid)
+            {
+                entry.person = Some(person.clone());
+            }
+        }
+    }
+
+    pub fn remove_person(&mut self, person_uuid: &String) {
+        for entry in self.entries.items.iter_mut() {
+            if entry
+                .person
+                .as_ref()
+                .map_or(false, |person| &person.uuid == person_uuid)
+            {
+                entry.person = None;
+            }
+        }
+    }
+
+    fn entry_mut(&mut self, face_uuid: &String) -> Option<&mut Entry> {
+        self.entries.get_mut(face_uuid)
+    }
+
+    // distances to every face measured by `encoder`, in no particular order
+    pub fn distances(
+        &self,
+        measurements: &[f64],
+        encoder: &str,
+        min_quality: f64,
+    ) -> Vec<(&Entry, f64)> {
+        self.entries
+            .items
+            .iter()
+            .filter(|entry| entry.encoder == encoder)
+            .filter(|entry| is_good_enough(entry.quality, min_quality))
+            .map(|entry| (entry, distance(&entry.measurements, measurements)))
+            .collect()
+    }
+}
+
+// stored face of any photo, for similarity search
+pub struct LibraryEntry {
+    pub face_uuid: String,
+    pub photo_uuid: String,
+    pub encoder: String,
+    pub measurements: Vec<f64>,
+}
+
+impl FaceEntry for LibraryEntry {
+    fn face_uuid(&self) -> &String {
+        &self.face_uuid
+    }
+}
+
+pub struct Library {
+    entries: Entries<LibraryEntry>,
+}
+
+impl Default for Library {
+    fn default() -> Self {
+        Self {
+            entries: Entries::new(),
+        }
+    }
+}
+
+impl Library {
+    pub fn load() -> Self {
+        let mut library = Self::default();
+        for face in Face::all() {
+            library.insert(&face);
+        }
+        library
+    }
+
+    pub fn entries(&self) -> &[LibraryEntry] {
+        &self.entries.items
+    }
+
+    // faces without measurements can't be compared and are left out
+    pub fn insert(&mut self, face: &Face) {
+        self.remove(&face.uuid);
+        let measurements = face.deserialize_measurements();
+        if measurements.is_empty() {
+            return;
+        }
+        self.entries.insert(LibraryEntry {
+            face_uuid: face.uuid.clone(),
+            photo_uuid: face.photo_uuid.clone(),
+            encoder: face.encoder.clone(),
+            measurements: measurements,
+        });
+    }
+
+    pub fn remove(&mut self, face_uuid: &String) {
+        self.entries.remove(face_uuid);
+    }
+}
+
+pub fn closest(mut distances: Vec<(&Entry, f64)>, k: usize) -> Vec<(&Entry, f64)> {
+    if k == 0 {
+        return Vec::new();
+    }
+    if distances.len() > k {
+        distances.select_nth_unstable_by(k - 1, |a, b| a.1.total_cmp(&b.1));
+        distances.truncate(k);
+    }
+    distances.sort_by(|a, b| a.1.total_cmp(&b.1));
+    distances
+}
+
+// euclidean, as dlib compares its embeddings
+pub fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f64>()
+        .sqrt()
+}
+
+// of distances between faces, robust to a few far ones
+pub fn median(mut distances: Vec<f64>) -> Option<f64> {
+    if distances.is_empty() {
+        return None;
+    }
+    distances.sort_by(|a, b| a.total_cmp(b));
+    let middle = distances.len() / 2;
+    match distances.len() % 2 {
+        0 => Some((distances[middle - 1] + distances[middle]) / 2.0),
+        _ => Some(distances[middle]),
+    }
+}
+
+// runs `f` on the shared index, loading it first if needed
+pub fn with_index<T, F: FnOnce(&mut Index) -> T>(f: F) -> T {
+    let mut index = INDEX.lock().unwrap();
+    f(index.get_or_insert_with(Index::load))
+}
+
+// changes are applied only to a loaded index, otherwise they are read on load
+fn if_loaded<F: FnOnce(&mut Index)>(f: F) {
+    if let Some(index) = INDEX.lock().unwrap().as_mut() {
+        f(index);
+    }
+}
+
+// runs `f` on the shared library, loading it first if needed
+pub fn with_library<T, F: FnOnce(&mut Library) -> T>(f: F) -> T {
+    let mut library = LIBRARY.lock().unwrap();
+    f(library.get_or_insert_with(Library::load))
+}
+
+fn if_library_loaded<F: FnOnce(&mut Library)>(f: F) {
+    if let Some(library) = LIBRARY.lock().unwrap().as_mut() {
+        f(library);
+    }
+}
+
+pub fn created(face: &Face) {
+    if_library_loaded(|library| library.insert(face));
+}
+
+pub fn moderated(face: &Face) {
+    // queried before locking, so other threads don't wait for the database
+    let person = face.person();
+    if_loaded(|index| index.insert(face, person));
+}
+
+pub fn deleted(face_uuid: &String) {
+    if_loaded(|index| index.remove(face_uuid));
+    if_library_loaded(|library| library.remove(face_uuid));
+}
+
+pub fn person_changed(person: &Person) {
+    if_loaded(|index| index.update_person(person));
+}
+
+pub fn person_merged(person: &Person, merged_person_uuid: &String) {
+    if_loaded(|index| index.reassign_person(merged_person_uuid, person));
+}
+
+pub fn person_deleted(person_uuid: &String) {
+    if_loaded(|index| index.remove_person(person_uuid));
+}
+
+// measurements and quality of a stored face changed. Faces stored without measurements,
+// as when encoding failed, join the library once they are measured
+pub fn measured(face: &Face) {
+    let measurements = face.deserialize_measurements();
+    if_loaded(|index| {
+        if let Some(entry) = index.entry_mut(&face.uuid) {
+            entry.measurements = measurements;
+            entry.encoder = face.encoder.clone();
+            entry.quality = face.quality;
+        }
+    });
+    if_library_loaded(|library| library.insert(face));
+}
@@ -0,0 +1,72 @@
+use crate::detection::options::{env_var, optional_env_var};
+use crate::recognition::scoring::Strategy;
+use dotenvy::dotenv;
+
+pub const DEFAULT_MIN_QUALITY: f64 = 0.0;
+pub const DEFAULT_TOP_K: usize = 10;
+pub const DEFAULT_STRATEGY: Strategy = Strategy::BestDistance;
+pub const DEFAULT_TOP_N: usize = 3;
+pub const DEFAULT_MIN_MARGIN: f64 = 0.05;
+pub const DEFAULT_MIN_VOTE_MARGIN: f64 = 0.2;
+pub const DEFAULT_MAX_DISTANCE: f64 = 0.6;
+
+pub struct Options {
+    // faces with lower quality score are not used for matching
+    pub min_quality: f64,
+    // how many closest faces are returned
+    pub top_k: usize,
+    // how faces of a person are combined into the person score
+    pub strategy: Strategy,
+    // faces per person averaged by the mean of top-n strategy
+    pub top_n: usize,
+    // best person must be ahead of the runner-up by this distance to be known, otherwise it's ambiguous
+    pub min_margin: f64,
+    // same for the k-NN vote, by this share of the k votes
+    pub min_vote_margin: f64,
+    // global threshold: faces further from every person are unknown
+    pub max_distance: f64,
+    // where photos come from, e.g. a camera name, for a source threshold override
+    pub source: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            min_quality: DEFAULT_MIN_QUALITY,
+            top_k: DEFAULT_TOP_K,
+            strategy: DEFAULT_STRATEGY,
+            top_n: DEFAULT_TOP_N,
+            min_margin: DEFAULT_MIN_MARGIN,
+            min_vote_margin: DEFAULT_MIN_VOTE_MARGIN,
+            max_distance: DEFAULT_MAX_DISTANCE,
+            source: None,
+        }
+    }
+}
+
+impl Options {
+    pub fn from_env() -> Self {
+        dotenv().ok();
+        let defaults = Self::default();
+
+        Self {
+            min_quality: env_var("RECOGNITION_MIN_QUALITY", defaults.min_quality),
+            top_k: env_var("RECOGNITION_TOP_K", defaults.top_k),
+            strategy: optional_env_var::<String>("RECOGNITION_STRATEGY")
+                .map_or(defaults.strategy, |value| Strategy::parse(&value)),
+            top_n: env_var("RECOGNITION_TOP_N", defaults.top_n),
+            min_margin: env_var("RECOGNITION_MIN_MARGIN", defaults.min_margin),
+            min_vote_margin: env_var("RECOGNITION_MIN_VOTE_MARGIN", defaults.min_vote_margin),
+            max_distance: env_var("RECOGNITION_MAX_DISTANCE", defaults.max_distance),
+            source: optional_env_var("RECOGNITION_SOURCE").or(defaults.source),
+        }
+    }
+
+    // candidate scores are vote shares for the k-NN vote and distances for other strategies
+    pub fn strategy_min_margin(&self) -> f64 {
+        match self.strategy {
+            Strategy::KnnVote => self.min_vote_margin,
+            _ => self.min_margin,
+        }
+    }
+}
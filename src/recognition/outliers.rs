@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::db::person::Person;
+use crate::recognition::index::{self, distance, median, Entry};
+use crate::recognition::options::Options;
+use crate::recognition::thresholds::Thresholds;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    // another person's faces are closer than the person's own ones
+    CloserToOther,
+    // further from the person's other faces than the threshold
+    FarFromPerson,
+}
+
+// moderated face that is likely assigned to the wrong person
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Outlier {
+    pub face_uuid: String,
+    pub person: Person,
+    pub reason: Reason,
+    // median distance to the person's other faces, none for the person's only face
+    pub distance: Option<f64>,
+    pub threshold: f64,
+    // closest other person to reassign the face to, and median distance to their faces
+    pub suggested_person: Option<Person>,
+    pub suggested_distance: Option<f64>,
+}
+
+// moderated faces of the index, worst first
+pub fn call() -> Vec<Outlier> {
+    let thresholds = Thresholds::load(&Options::from_env());
+    index::with_index(|index| find(index.entries(), &thresholds))
+}
+
+// every face is compared with the other faces of its person and faces of other persons
+// by the median distance, so a few wrong faces of a person don't make the right ones look
+// wrong too. Only faces measured by the same model are compared
+pub fn find(entries: &[Entry], thresholds: &Thresholds) -> Vec<Outlier> {
+    let mut faces_by_person: HashMap<(&String, &String), (&Person, Vec<&Entry>)> = HashMap::new();
+    for entry in entries.iter() {
+        let Some(person) = &entry.person else { continue };
+        faces_by_person
+            .entry((&entry.encoder, &person.uuid))
+            .or_insert((person, Vec::new()))
+            .1
+            .push(entry);
+    }
+
+    let mut outliers = Vec::new();
+    for entry in entries.iter() {
+        let Some(person) = &entry.person else { continue };
+        let threshold = thresholds.for_person(person);
+
+        let own_distance = median(
+            faces_by_person[&(&entry.encoder, &person.uuid)]
+                .1
+                .iter()
+                .filter(|other| other.face_uuid != entry.face_uuid)
+                .map(|other| distance(&other.measurements, &entry.measurements))
+                .collect(),
+        );
+
+        let nearest = faces_by_person
+            .iter()
+            .filter(|((encoder, uuid), _)| *encoder == &entry.encoder && *uuid != &person.uuid)
+            .filter_map(|(_, (other_person, faces))| {
+                let distances = faces
+                    .iter()
+                    .map(|other| distance(&other.measurements, &entry.measurements))
+                    .collect();
+                median(distances).map(|median_distance| (*other_person, median_distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        let reason = match nearest {
+            Some((_, nearest_distance))
+                if nearest_distance < own_distance.unwrap_or(threshold) =>
+            {
+                Reason::CloserToOther
+            }
+            _ if own_distance.map_or(false, |own_distance| own_distance > threshold) => {
+                Reason::FarFromPerson
+            }
+            _ => continue,
+        };
+        let suggested = nearest.filter(|(_, nearest_distance)| {
+            reason == Reason::CloserToOther || *nearest_distance < threshold
+        });
+
+        outliers.push(Outlier {
+            face_uuid: entry.face_uuid.clone(),
+            person: person.clone(),
+            reason: reason,
+            distance: own_distance,
+            threshold: threshold,
+            suggested_person: suggested.map(|(person, _)| person.clone()),
+            suggested_distance: suggested.map(|(_, nearest_distance)| nearest_distance),
+        });
+    }
+
+    outliers.sort_by(|a, b| {
+        (a.reason != Reason::CloserToOther)
+            .cmp(&(b.reason != Reason::CloserToOther))
+            .then(b.distance.unwrap_or(0.0).total_cmp(&a.distance.unwrap_or(0.0)))
+    });
+    outliers
+}
+
+impl fmt::Display for Outlier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: distance {} (threshold: {:.2})",
+            self.face_uuid,
+            self.person.name,
+            crate::detection::format_score(self.distance),
+            self.threshold
+        )
+        .unwrap();
+        if let Some(person) = &self.suggested_person {
+            write!(
+                f,
+                ", closer to {}: {}",
+                person.name,
+                crate::detection::format_score(self.suggested_distance)
+            )
+            .unwrap();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::db::person::Person;
+use crate::recognition::index::{distance, Entry};
+use crate::recognition::thresholds::Thresholds;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    // distance to the closest face of a person
+    BestDistance,
+    // share of the k closest faces that belong to a person
+    KnnVote,
+    // distance to the mean embedding of a person
+    Centroid,
+    // mean distance to the n closest faces of a person
+    MeanTopN,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Candidate {
+    pub person: Person,
+    // strategy score, lower is better
+    pub score: f64,
+    // distance to the closest face of the person
+    pub distance: f64,
+    // score difference to the next candidate, none for the last one
+    pub margin: Option<f64>,
+    // maximum distance for the person to match
+    pub threshold: f64,
+}
+
+impl Strategy {
+    // "best", "knn", "centroid" or "mean_top_n"
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "best" => Self::BestDistance,
+            "knn" => Self::KnnVote,
+            "centroid" => Self::Centroid,
+            "mean_top_n" => Self::MeanTopN,
+            _ => panic!("Unknown matching strategy: {}", value),
+        }
+    }
+}
+
+// `distances` from the query to every comparable face, persons ranked best first
+pub fn rank(
+    distances: &[(&Entry, f64)],
+    measurements: &[f64],
+    strategy: Strategy,
+    k: usize,
+    n: usize,
+    thresholds: &Thresholds,
+) -> Vec<Candidate> {
+    let mut faces_by_person: HashMap<&String, (&Person, Vec<(&Entry, f64)>)> = HashMap::new();
+    for (entry, face_distance) in distances.iter() {
+        if let Some(person) = &entry.person {
+            faces_by_person
+                .entry(&person.uuid)
+                .or_insert((person, Vec::new()))
+                .1
+                .push((entry, *face_distance));
+        }
+    }
+
+    let votes = match strategy {
+        Strategy::KnnVote => knn_votes(distances, k),
+        _ => HashMap::new(),
+    };
+
+    let mut candidates: Vec<Candidate> = faces_by_person
+        .into_values()
+        .filter_map(|(person, mut faces)| {
+            faces.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let best = faces[0].1;
+
+            let score = match strategy {
+                Strategy::BestDistance => best,
+                Strategy::KnnVote => {
+                    let votes = *votes.get(&person.uuid)?;
+                    1.0 - votes as f64 / k.min(distances.len()) as f64
+                }
+                Strategy::Centroid => {
+                    let embeddings: Vec<&Vec<f64>> =
+                        faces.iter().map(|(entry, _)| &entry.measurements).collect();
+                    distance(&centroid(&embeddings), measurements)
+                }
+                Strategy::MeanTopN => {
+                    let top = &faces[..n.max(1).min(faces.len())];
+                    top.iter()
+                        .map(|(_, face_distance)| face_distance)
+                        .sum::<f64>()
+                        / top.len() as f64
+                }
+            };
+
+            Some(Candidate {
+                person: person.clone(),
+                score: score,
+                distance: best,
+                margin: None,
+                threshold: thresholds.for_person(person),
+            })
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| {
+        a.score
+            .total_cmp(&b.score)
+            .then(a.distance.total_cmp(&b.distance))
+    });
+    for i in 1..candidates.len() {
+        candidates[i - 1].margin = Some(candidates[i].score - candidates[i - 1].score);
+    }
+    candidates
+}
+
+// faces without a person take their vote slot too
+fn knn_votes(distances: &[(&Entry, f64)], k: usize) -> HashMap<String, usize> {
+    let mut nearest: Vec<&(&Entry, f64)> = distances.iter().collect();
+    nearest.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    let mut votes = HashMap::new();
+    for (entry, _) in nearest.into_iter().take(k) {
+        if let Some(person) = &entry.person {
+            *votes.entry(person.uuid.clone()).or_insert(0) += 1;
+        }
+    }
+    votes
+}
+
+pub fn centroid(embeddings: &[&Vec<f64>]) -> Vec<f64> {
+    let size = embeddings.first().map_or(0, |embedding| embedding.len());
+    let mut result = vec![0.0; size];
+    for embedding in embeddings.iter() {
+        for (sum, value) in result.iter_mut().zip(embedding.iter()) {
+            *sum += value;
+        }
+    }
+    result
+        .into_iter()
+        .map(|sum| sum / embeddings.len() as f64)
+        .collect()
+}
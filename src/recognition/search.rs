@@ -0,0 +1,238 @@
+use dotenvy::dotenv;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::db::face::Face;
+use crate::db::person::Person;
+use crate::db::photo::Photo;
+use crate::detection;
+use crate::detection::options::env_var;
+use crate::detection::orientation;
+use crate::media;
+use crate::recognition;
+use crate::recognition::index::{self, distance};
+
+pub const DEFAULT_LIMIT: usize = 50;
+
+pub struct Options {
+    // faces further than this are not similar, the recognition threshold by default
+    pub max_distance: f64,
+    // how many photos are returned
+    pub limit: usize,
+    // photos stored on or after this day, "YYYY-MM-DD". Photos stored before dates were
+    // recorded have none and are left out by either date filter
+    pub from: Option<String>,
+    // photos stored on or before this day, "YYYY-MM-DD"
+    pub to: Option<String>,
+    pub source: Option<String>,
+}
+
+// photo with a face similar to the query one
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Hit {
+    pub photo_uuid: String,
+    pub created_at: String,
+    pub source: String,
+    // closest face of the photo
+    pub face_uuid: String,
+    // person of the face, none for unlabelled faces
+    pub person: Option<Person>,
+    pub distance: f64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            max_distance: recognition::options::DEFAULT_MAX_DISTANCE,
+            limit: DEFAULT_LIMIT,
+            from: None,
+            to: None,
+            source: None,
+        }
+    }
+}
+
+impl Options {
+    pub fn from_env() -> Self {
+        dotenv().ok();
+        let defaults = Self::default();
+
+        Self {
+            max_distance: env_var(
+                "SEARCH_MAX_DISTANCE",
+                recognition::options::Options::from_env().max_distance,
+            ),
+            limit: env_var("SEARCH_LIMIT", defaults.limit),
+            ..defaults
+        }
+    }
+
+    fn photo_conditions(&self) -> String {
+        let mut conditions = vec![String::from("1 = 1")];
+        if let Some(from) = &self.from {
+            conditions.push(format!(
+                "date(created_at) >= '{}'",
+                from.replace('\'', "''")
+            ));
+        }
+        if let Some(to) = &self.to {
+            conditions.push(format!("date(created_at) <= '{}'", to.replace('\'', "''")));
+        }
+        if let Some(source) = &self.source {
+            conditions.push(format!("source = '{}'", source.replace('\'', "''")));
+        }
+        conditions.join(" AND ")
+    }
+}
+
+// checks a date filter is a day of the calendar, "YYYY-MM-DD"
+pub fn parse_date(value: &str) -> Result<String, Box<dyn Error>> {
+    let invalid = || format!("Invalid date, expected YYYY-MM-DD: {}", value);
+    let parts: Vec<&str> = value.split('-').collect();
+    let digits =
+        |part: &str, len: usize| part.len() == len && part.chars().all(|c| c.is_ascii_digit());
+    if parts.len() != 3 || !digits(parts[0], 4) || !digits(parts[1], 2) || !digits(parts[2], 2) {
+        return Err(invalid().into());
+    }
+    let [year, month, day] =
+        [parts[0], parts[1], parts[2]].map(|part| part.parse::<u32>().unwrap());
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return Err(invalid().into()),
+    };
+    if day == 0 || day > days {
+        return Err(invalid().into());
+    }
+    Ok(value.to_string())
+}
+
+// photos with faces similar to a stored face, other than the face itself
+pub fn by_face(face_uuid: &String, options: &Options) -> Result<Vec<Hit>, Box<dyn Error>> {
+    let Some(face) = Face::find_by_uuid(face_uuid) else {
+        return Err(format!("Face not found: {}", face_uuid).into());
+    };
+    Ok(search(
+        &face.deserialize_measurements(),
+        &face.encoder,
+        Some(face_uuid),
+        options,
+    ))
+}
+
+// photos with faces similar to the largest face of an image, nothing is stored
+pub fn by_image(path: &String, options: &Options) -> Result<Vec<Hit>, Box<dyn Error>> {
+    let image = orientation::apply(media::open(path)?, orientation::read(path));
+    let photo = detection::analyze(image, &detection::options::Options::from_env());
+
+    match photo.largest_face() {
+        None => Err(format!("No face found: {}", path).into()),
+        Some(face) => Ok(search(&face.measurements, &face.encoder, None, options)),
+    }
+}
+
+// every stored face measured by `encoder` is compared, labelled or not, from the in-memory
+// library. Photos are ranked by their closest face
+pub fn search(
+    measurements: &[f64],
+    encoder: &String,
+    exclude: Option<&String>,
+    options: &Options,
+) -> Vec<Hit> {
+    let photos: HashMap<String, Photo> = Photo::where_all(&options.photo_conditions())
+        .into_iter()
+        .map(|photo| (photo.uuid.clone(), photo))
+        .collect();
+
+    let mut ranked: Vec<(String, f64)> = index::with_library(|library| {
+        let mut closest: HashMap<&String, (&String, f64)> = HashMap::new();
+        for entry in library.entries().iter() {
+            if &entry.encoder != encoder
+                || Some(&entry.face_uuid) == exclude
+                || !photos.contains_key(&entry.photo_uuid)
+            {
+                continue;
+            }
+
+            let face_distance = distance(&entry.measurements, measurements);
+            if face_distance >= options.max_distance {
+                continue;
+            }
+            let best = closest
+                .entry(&entry.photo_uuid)
+                .or_insert((&entry.face_uuid, face_distance));
+            if face_distance < best.1 {
+                *best = (&entry.face_uuid, face_distance);
+            }
+        }
+
+        closest
+            .into_values()
+            .map(|(face_uuid, face_distance)| (face_uuid.clone(), face_distance))
+            .collect()
+    });
+    ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+    ranked.truncate(options.limit);
+
+    // only faces and persons of the hits are read from the database
+    let faces: HashMap<String, Face> = Face::where_all(&format!(
+        "uuid IN({})",
+        quoted(ranked.iter().map(|hit| &hit.0))
+    ))
+    .into_iter()
+    .map(|face| (face.uuid.clone(), face))
+    .collect();
+    let persons: HashMap<String, Person> = Person::where_all(&format!(
+        "uuid IN({})",
+        quoted(faces.values().map(|face| &face.person_uuid))
+    ))
+    .into_iter()
+    .map(|person| (person.uuid.clone(), person))
+    .collect();
+
+    ranked
+        .into_iter()
+        .filter_map(|(face_uuid, face_distance)| {
+            let face = faces.get(&face_uuid)?;
+            let photo = &photos[&face.photo_uuid];
+            Some(Hit {
+                photo_uuid: photo.uuid.clone(),
+                created_at: photo.created_at.clone(),
+                source: photo.source.clone(),
+                face_uuid: face_uuid,
+                person: persons.get(&face.person_uuid).cloned(),
+                distance: face_distance,
+            })
+        })
+        .collect()
+}
+
+fn quoted<'a>(values: impl Iterator<Item = &'a String>) -> String {
+    values
+        .map(|value| format!("'{}'", value.replace('\'', "''")))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+impl fmt::Display for Hit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.4} {} {} {} {} {}",
+            self.distance,
+            self.photo_uuid,
+            self.created_at,
+            self.source,
+            self.face_uuid,
+            self.person
+                .as_ref()
+                .map_or("-", |person| person.name.as_str())
+        )
+    }
+}
@@ -0,0 +1,71 @@
+use std::error::Error;
+
+use crate::db::person::Person;
+use crate::db::source::Source;
+use crate::recognition::options::Options;
+
+// maximum distance to a person's faces for a match. A person override wins over the
+// override of the source photos come from, which wins over the global threshold
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Thresholds {
+    pub global: f64,
+    pub source: Option<f64>,
+}
+
+impl Thresholds {
+    pub fn load(options: &Options) -> Self {
+        Self {
+            global: options.max_distance,
+            source: options
+                .source
+                .as_ref()
+                .and_then(Source::find_by_name)
+                .map(|source| source.max_distance),
+        }
+    }
+
+    // for faces not matched to any person
+    pub fn default_value(&self) -> f64 {
+        self.source.unwrap_or(self.global)
+    }
+
+    pub fn for_person(&self, person: &Person) -> f64 {
+        person.max_distance.unwrap_or(self.default_value())
+    }
+}
+
+// `target` is "person" or "source", `value` is a distance or "reset" to drop the override
+pub fn set(target: &str, name: &String, value: &str) -> Result<(), Box<dyn Error>> {
+    let max_distance = match value {
+        "reset" => None,
+        _ => Some(
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|distance| distance.is_finite() && *distance > 0.0)
+                .ok_or_else(|| format!("Invalid threshold: {}", value))?,
+        ),
+    };
+
+    match target {
+        "person" => {
+            let persons = Person::where_all(&format!("name = '{}'", name.replace('\'', "''")));
+            if persons.is_empty() {
+                return Err(format!("Person is not found: {}", name).into());
+            }
+            for person in persons.iter() {
+                Person::set_max_distance(&person.uuid, max_distance);
+            }
+        }
+        "source" => match max_distance {
+            Some(max_distance) => {
+                Source::set(name, max_distance);
+            }
+            None => {
+                Source::delete(name);
+            }
+        },
+        _ => return Err(format!("Unknown threshold target: {}", target).into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::db::person::Person;
+use crate::recognition::scoring::Candidate;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    // confidence is 0..1, 1 for an identical face
+    Known(Person, f64),
+    // several persons are close and too near to each other to pick one
+    Ambiguous(Vec<Person>),
+    Unknown,
+}
+
+impl Verdict {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Known(_, _) => "known",
+            Self::Ambiguous(_) => "ambiguous",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    pub fn person(&self) -> Option<&Person> {
+        match self {
+            Self::Known(person, _) => Some(person),
+            _ => None,
+        }
+    }
+}
+
+// `candidates` ranked best first. Persons further than their threshold are not considered,
+// the best one is known if its score is ahead of the runner-up by at least `min_margin`,
+// given on the scale of the strategy score
+pub fn decide(candidates: &[Candidate], min_margin: f64) -> Verdict {
+    let close: Vec<&Candidate> = candidates
+        .iter()
+        .filter(|candidate| candidate.distance < candidate.threshold)
+        .collect();
+
+    let Some(best) = close.first() else {
+        return Verdict::Unknown;
+    };
+
+    let contenders: Vec<Person> = close
+        .iter()
+        .filter(|candidate| candidate.score - best.score < min_margin)
+        .map(|candidate| candidate.person.clone())
+        .collect();
+
+    if contenders.len() > 1 {
+        return Verdict::Ambiguous(contenders);
+    }
+    Verdict::Known(best.person.clone(), 1.0 - best.distance / best.threshold)
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Known(person, confidence) => {
+                write!(f, "known: {} (confidence: {:.2})", person.name, confidence)
+            }
+            Self::Ambiguous(persons) => {
+                let names: Vec<&str> = persons.iter().map(|person| person.name.as_str()).collect();
+                write!(f, "ambiguous: {}", names.join(", "))
+            }
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
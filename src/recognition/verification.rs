@@ -0,0 +1,191 @@
+use dlib_face_recognition::Rectangle;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+
+use crate::db::face::Face;
+use crate::detection;
+use crate::detection::orientation;
+use crate::media;
+use crate::recognition::index::distance;
+use crate::recognition::options::Options;
+use crate::recognition::thresholds::Thresholds;
+
+// face compared on one side of a verification
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Side {
+    // path to the image or uuid of the stored face
+    pub input: String,
+    // position of the compared face, the largest one, among faces found in the image
+    pub face: usize,
+    pub faces: usize,
+    #[serde(
+        rename = "box",
+        serialize_with = "crate::detection::face_image::serialize_location"
+    )]
+    pub location: Option<Rectangle>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Verification {
+    pub left: Side,
+    pub right: Side,
+    pub distance: f64,
+    pub threshold: f64,
+    pub same_person: bool,
+}
+
+struct Measured {
+    side: Side,
+    encoder: String,
+    measurements: Vec<f64>,
+}
+
+pub fn images(left_path: &String, right_path: &String) -> Result<Verification, Box<dyn Error>> {
+    images_with_options(
+        left_path,
+        right_path,
+        &detection::options::Options::from_env(),
+        &Options::from_env(),
+    )
+}
+
+// the largest face of each image is compared, as other faces are usually bystanders.
+// Nothing is stored
+pub fn images_with_options(
+    left_path: &String,
+    right_path: &String,
+    detection_options: &detection::options::Options,
+    options: &Options,
+) -> Result<Verification, Box<dyn Error>> {
+    verify(
+        &measure_image(left_path, detection_options)?,
+        &measure_image(right_path, detection_options)?,
+        Thresholds::load(options).default_value(),
+    )
+}
+
+pub fn faces(left_uuid: &String, right_uuid: &String) -> Result<Verification, Box<dyn Error>> {
+    faces_with_options(left_uuid, right_uuid, &Options::from_env())
+}
+
+// stored faces are compared as they are, with the stricter threshold of the persons
+// of both faces if any
+pub fn faces_with_options(
+    left_uuid: &String,
+    right_uuid: &String,
+    options: &Options,
+) -> Result<Verification, Box<dyn Error>> {
+    let left = find_face(left_uuid)?;
+    let right = find_face(right_uuid)?;
+    let thresholds = Thresholds::load(options);
+    let threshold = [left.person(), right.person()]
+        .iter()
+        .flatten()
+        .map(|person| thresholds.for_person(person))
+        .reduce(f64::min)
+        .unwrap_or(thresholds.default_value());
+
+    verify(&measure_face(&left), &measure_face(&right), threshold)
+}
+
+fn find_face(uuid: &String) -> Result<Face, Box<dyn Error>> {
+    Face::find_by_uuid(uuid).ok_or_else(|| format!("Face not found: {}", uuid).into())
+}
+
+fn measure_image(
+    path: &String,
+    detection_options: &detection::options::Options,
+) -> Result<Measured, Box<dyn Error>> {
+    let image = orientation::apply(media::open(path)?, orientation::read(path));
+    let photo = detection::analyze(image, detection_options);
+    let Some(face) = photo.largest_face() else {
+        return Err(format!("No face found: {}", path).into());
+    };
+
+    Ok(Measured {
+        side: Side {
+            input: path.clone(),
+            face: photo
+                .faces
+                .iter()
+                .position(|other| other.uuid == face.uuid)
+                .unwrap(),
+            faces: photo.faces.len(),
+            location: face.face_location,
+        },
+        encoder: face.encoder.clone(),
+        measurements: face.measurements.clone(),
+    })
+}
+
+fn measure_face(face: &Face) -> Measured {
+    Measured {
+        side: Side {
+            input: face.uuid.clone(),
+            face: 0,
+            faces: 1,
+            location: Some(face.location()),
+        },
+        encoder: face.encoder.clone(),
+        measurements: face.deserialize_measurements(),
+    }
+}
+
+fn verify(
+    left: &Measured,
+    right: &Measured,
+    threshold: f64,
+) -> Result<Verification, Box<dyn Error>> {
+    if left.encoder != right.encoder {
+        return Err("Faces are measured by different models, re-encode them first".into());
+    }
+
+    let face_distance = distance(&left.measurements, &right.measurements);
+    Ok(Verification {
+        left: left.side.clone(),
+        right: right.side.clone(),
+        distance: face_distance,
+        threshold: threshold,
+        same_person: face_distance < threshold,
+    })
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (face {} of {}",
+            self.input,
+            self.face + 1,
+            self.faces
+        )
+        .unwrap();
+        if let Some(location) = self.location {
+            write!(
+                f,
+                ", box: {} {} {} {}",
+                location.left, location.top, location.right, location.bottom
+            )
+            .unwrap();
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Left: {}\r\n", self.left).unwrap();
+        write!(f, "Right: {}\r\n", self.right).unwrap();
+        write!(
+            f,
+            "Distance: {:.4} (threshold: {:.2})\r\n",
+            self.distance, self.threshold
+        )
+        .unwrap();
+        match self.same_person {
+            true => write!(f, "Same person"),
+            false => write!(f, "Different persons"),
+        }
+    }
+}
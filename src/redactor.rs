@@ -0,0 +1,359 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use ffmpeg::format::{input, output, Pixel};
+use ffmpeg::media::Type;
+use ffmpeg::software::scaling::{context::Context, flag::Flags};
+use ffmpeg::util::frame::video::Video;
+use image::{DynamicImage, RgbImage};
+use std::error::Error;
+use std::path::Path;
+
+use crate::detection;
+use crate::detection::face_image::FaceImage;
+use crate::detection::options::Options;
+use crate::media;
+use crate::recognition;
+use dlib_face_recognition::Rectangle;
+
+// redacted area is grown by this percent of the face box to cover hair and chin
+const MARGIN: f64 = 25.0;
+// pixelated face is this many blocks wide
+const PIXELATE_BLOCKS: u32 = 8;
+
+pub enum Target {
+    All,
+    Unknown,
+    // uuids of persons whose faces stay visible
+    AllExcept(Vec<String>),
+}
+
+pub enum Style {
+    Blur,
+    Pixelate,
+}
+
+impl Target {
+    // "all", "unknown" or "except=name_1,name_2"
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value {
+            "all" => Ok(Self::All),
+            "unknown" => Ok(Self::Unknown),
+            _ => match value.strip_prefix("except=") {
+                Some(names) => {
+                    let names: Vec<&str> = names.split(',').map(|name| name.trim()).collect();
+                    let quoted: Vec<String> = names
+                        .iter()
+                        .map(|name| format!("'{}'", name.replace('\'', "''")))
+                        .collect();
+                    let persons = crate::db::person::Person::where_all(&format!(
+                        "name IN({})",
+                        quoted.join(", ")
+                    ));
+
+                    // a mistyped name would redact the person it was meant to keep visible
+                    let missing: Vec<&str> = names
+                        .into_iter()
+                        .filter(|name| !persons.iter().any(|person| &person.name == name))
+                        .collect();
+                    if !missing.is_empty() {
+                        return Err(format!("Unknown persons: {}", missing.join(", ")).into());
+                    }
+
+                    Ok(Self::AllExcept(
+                        persons.into_iter().map(|person| person.uuid).collect(),
+                    ))
+                }
+                None => Err(format!("Unknown redaction target: {}", value).into()),
+            },
+        }
+    }
+}
+
+impl Style {
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value {
+            "blur" => Ok(Self::Blur),
+            "pixelate" => Ok(Self::Pixelate),
+            _ => Err(format!("Unknown redaction style: {}", value).into()),
+        }
+    }
+}
+
+// writes a copy of photo or video at `input_path` with faces redacted,
+// returns how many faces were redacted. Nothing is written to the database.
+pub fn call(
+    input_path: &String,
+    output_path: &String,
+    target: &Target,
+    style: &Style,
+) -> Result<usize, Box<dyn Error>> {
+    let options = Options {
+        // every bystander counts, however small or uncertain
+        min_face_size: 0,
+        min_confidence: None,
+        ..Options::from_env()
+    };
+    let recognition_options = recognition::options::Options::from_env();
+
+    match media::sniff(input_path) {
+        media::Kind::Image(_) => {
+            let exif_orientation = detection::orientation::read(input_path);
+            let mut image =
+                detection::orientation::apply(media::open(input_path)?, exif_orientation);
+            let count = redact_image(&mut image, target, style, &options, &recognition_options);
+            image.save(output_path)?;
+            Ok(count)
+        }
+        // animated gifs and anything else ffmpeg can read
+        _ => redact_video(
+            input_path,
+            output_path,
+            target,
+            style,
+            &options,
+            &recognition_options,
+        ),
+    }
+}
+
+pub fn redact_image(
+    image: &mut DynamicImage,
+    target: &Target,
+    style: &Style,
+    options: &Options,
+    recognition_options: &recognition::options::Options,
+) -> usize {
+    let photo = detection::analyze(image.clone(), options);
+    let mut count = 0;
+
+    for face in photo.faces.iter() {
+        if should_redact(face, target, recognition_options) {
+            redact_area(image, &face.face_location.unwrap(), style);
+            count += 1;
+        }
+    }
+    count
+}
+
+pub fn redact_area(image: &mut DynamicImage, location: &Rectangle, style: &Style) {
+    let area = detection::crop_area(location, MARGIN, image.width(), image.height());
+    let (width, height) = (
+        (area.right - area.left) as u32,
+        (area.bottom - area.top) as u32,
+    );
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let face = image.crop_imm(area.left as u32, area.top as u32, width, height);
+    let redacted = match style {
+        Style::Blur => face.blur(width.max(height) as f32 / 6.0),
+        Style::Pixelate => {
+            let block = (width / PIXELATE_BLOCKS).max(1);
+            face.resize_exact(
+                (width / block).max(1),
+                (height / block).max(1),
+                image::imageops::FilterType::Triangle,
+            )
+            .resize_exact(width, height, image::imageops::FilterType::Nearest)
+        }
+    };
+
+    image::imageops::replace(image, &redacted, area.left, area.top);
+}
+
+fn should_redact(
+    face: &FaceImage,
+    target: &Target,
+    options: &recognition::options::Options,
+) -> bool {
+    match target {
+        Target::All => true,
+        Target::Unknown => known_person_uuid(face, options).is_none(),
+        Target::AllExcept(uuids) => match known_person_uuid(face, options) {
+            Some(uuid) => !uuids.contains(&uuid),
+            None => true,
+        },
+    }
+}
+
+fn known_person_uuid(face: &FaceImage, options: &recognition::options::Options) -> Option<String> {
+    let recognition = recognition::find_matches_for_measurements(
+        &face.measurements,
+        &face.encoder,
+        face.quality.map(|quality| quality.score),
+        options,
+    );
+
+    // ambiguous faces are not known well enough to stay visible
+    recognition
+        .verdict
+        .person()
+        .map(|person| person.uuid.clone())
+}
+
+// re-encodes the best video stream to H.264 (MPEG-4 as a fallback), other streams are dropped.
+// A GIF can't hold either, so animations are written to a video file
+fn redact_video(
+    input_path: &String,
+    output_path: &String,
+    target: &Target,
+    style: &Style,
+    options: &Options,
+    recognition_options: &recognition::options::Options,
+) -> Result<usize, Box<dyn Error>> {
+    let extension = Path::new(output_path)
+        .extension()
+        .and_then(|extension| extension.to_str());
+    if extension.map_or(false, |extension| extension.eq_ignore_ascii_case("gif")) {
+        return Err(format!(
+            "Redacted animations are written as video, use a .mp4 output: {}",
+            output_path
+        )
+        .into());
+    }
+
+    ffmpeg::init()?;
+
+    let mut ictx = input(input_path)?;
+    let mut octx = output(output_path)?;
+
+    let (video_stream_index, time_base, parameters) = {
+        let stream = ictx
+            .streams()
+            .best(Type::Video)
+            .ok_or(ffmpeg::Error::StreamNotFound)?;
+        (stream.index(), stream.time_base(), stream.parameters())
+    };
+
+    let mut decoder = ffmpeg::codec::context::Context::from_parameters(parameters)?
+        .decoder()
+        .video()?;
+    let (width, height) = (decoder.width(), decoder.height());
+
+    let codec = ffmpeg::encoder::find(ffmpeg::codec::Id::H264)
+        .or_else(|| ffmpeg::encoder::find(ffmpeg::codec::Id::MPEG4))
+        .ok_or(ffmpeg::Error::EncoderNotFound)?;
+    let global_header = octx
+        .format()
+        .flags()
+        .contains(ffmpeg::format::Flags::GLOBAL_HEADER);
+
+    let mut encoder = {
+        let mut stream = octx.add_stream(codec)?;
+        let mut encoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?
+            .encoder()
+            .video()?;
+        encoder.set_width(width);
+        encoder.set_height(height);
+        encoder.set_format(Pixel::YUV420P);
+        encoder.set_time_base(time_base);
+        encoder.set_frame_rate(decoder.frame_rate());
+        if global_header {
+            encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
+
+        let encoder = encoder.open_as(codec)?;
+        stream.set_parameters(&encoder);
+        encoder
+    };
+
+    octx.write_header()?;
+    let output_time_base = octx.stream(0).unwrap().time_base();
+
+    let mut to_rgb = Context::get(
+        decoder.format(),
+        width,
+        height,
+        Pixel::RGB24,
+        width,
+        height,
+        Flags::BILINEAR,
+    )?;
+    let mut to_yuv = Context::get(
+        Pixel::RGB24,
+        width,
+        height,
+        Pixel::YUV420P,
+        width,
+        height,
+        Flags::BILINEAR,
+    )?;
+
+    let mut count = 0;
+
+    let mut receive_and_redact_decoded_frames = |decoder: &mut ffmpeg::decoder::Video,
+                                                 encoder: &mut ffmpeg::encoder::Video,
+                                                 octx: &mut ffmpeg::format::context::Output|
+     -> Result<(), ffmpeg::Error> {
+        let mut decoded = Video::empty();
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            let mut rgb_frame = Video::empty();
+            to_rgb.run(&decoded, &mut rgb_frame)?;
+
+            let mut image = frame_to_image(&rgb_frame);
+            count += redact_image(&mut image, target, style, options, recognition_options);
+            image_to_frame(&image, &mut rgb_frame);
+
+            let mut yuv_frame = Video::empty();
+            to_yuv.run(&rgb_frame, &mut yuv_frame)?;
+            yuv_frame.set_pts(decoded.timestamp());
+
+            encoder.send_frame(&yuv_frame)?;
+            write_encoded_packets(encoder, octx, time_base, output_time_base)?;
+        }
+        Ok(())
+    };
+
+    for (stream, packet) in ictx.packets() {
+        if stream.index() == video_stream_index {
+            decoder.send_packet(&packet)?;
+            receive_and_redact_decoded_frames(&mut decoder, &mut encoder, &mut octx)?;
+        }
+    }
+    decoder.send_eof()?;
+    receive_and_redact_decoded_frames(&mut decoder, &mut encoder, &mut octx)?;
+
+    encoder.send_eof()?;
+    write_encoded_packets(&mut encoder, &mut octx, time_base, output_time_base)?;
+    octx.write_trailer()?;
+
+    Ok(count)
+}
+
+fn write_encoded_packets(
+    encoder: &mut ffmpeg::encoder::Video,
+    octx: &mut ffmpeg::format::context::Output,
+    input_time_base: ffmpeg::Rational,
+    output_time_base: ffmpeg::Rational,
+) -> Result<(), ffmpeg::Error> {
+    let mut encoded = ffmpeg::Packet::empty();
+    while encoder.receive_packet(&mut encoded).is_ok() {
+        encoded.set_stream(0);
+        encoded.rescale_ts(input_time_base, output_time_base);
+        encoded.write_interleaved(octx)?;
+    }
+    Ok(())
+}
+
+// rows of ffmpeg frames can be padded, so pixels are copied row by row
+fn frame_to_image(frame: &Video) -> DynamicImage {
+    let (width, height, stride) = (frame.width(), frame.height(), frame.stride(0));
+    let row_size = width as usize * 3;
+    let mut pixels = Vec::with_capacity(row_size * height as usize);
+    for row in 0..height as usize {
+        pixels.extend_from_slice(&frame.data(0)[row * stride..row * stride + row_size]);
+    }
+
+    DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, pixels).unwrap())
+}
+
+fn image_to_frame(image: &DynamicImage, frame: &mut Video) {
+    let pixels = image.to_rgb8();
+    let stride = frame.stride(0);
+    let row_size = pixels.width() as usize * 3;
+    let data = frame.data_mut(0);
+    for (row, row_pixels) in pixels.as_raw().chunks(row_size).enumerate() {
+        data[row * stride..row * stride + row_size].copy_from_slice(row_pixels);
+    }
+}
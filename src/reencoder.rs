@@ -0,0 +1,96 @@
+use colored::Colorize;
+use dlib_face_recognition::Rectangle;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::thread;
+
+use crate::db::face::Face;
+use crate::db::photo::Photo;
+use crate::detection;
+use crate::detection::options::Options;
+use crate::storage;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub reencoded: usize,
+    // faces without a stored location, photo or original
+    pub failed: usize,
+}
+
+pub fn call() -> Summary {
+    call_with_options(&Options::library())
+}
+
+// runs in its own thread: until a face is re-encoded, recognition keeps comparing it
+// with faces of its old model only
+pub fn spawn() -> thread::JoinHandle<Summary> {
+    thread::spawn(call)
+}
+
+// re-encodes every face measured by a model other than the current encoder
+pub fn call_with_options(options: &Options) -> Summary {
+    let model = options.backend.encoder.model();
+    let mut summary = Summary::default();
+
+    let mut faces_by_photo: BTreeMap<String, Vec<Face>> = BTreeMap::new();
+    for face in Face::where_all(&format!("encoder IS NOT '{}'", model.replace('\'', "''"))) {
+        faces_by_photo
+            .entry(face.photo_uuid.clone())
+            .or_default()
+            .push(face);
+    }
+
+    for (photo_uuid, faces) in faces_by_photo.iter() {
+        println!("{}", photo_uuid.yellow());
+
+        let Some(photo) = Photo::find_by_uuid(photo_uuid) else {
+            println!("{} {}", "Photo is not found:".red(), photo_uuid);
+            summary.failed += faces.len();
+            continue;
+        };
+        let image = match storage::open_original(&photo.uuid, &photo.original_file_name()) {
+            Err(error) => {
+                println!("{} {:?}", "Original cannot be read:".red(), error);
+                summary.failed += faces.len();
+                continue;
+            }
+            Ok(image) => image,
+        };
+
+        // faces stored before locations were recorded can't be measured again
+        let (faces, unlocated): (Vec<&Face>, Vec<&Face>) = faces
+            .iter()
+            .partition(|face| detection::face_size(&face.location()) > 0);
+        summary.failed += unlocated.len();
+
+        let locations: Vec<Rectangle> = faces.iter().map(|face| face.location()).collect();
+        let landmarks = options
+            .backend
+            .landmark_predictor
+            .landmarks(&image, &locations);
+        let located: Vec<(Rectangle, Vec<(i64, i64)>)> =
+            locations.into_iter().zip(landmarks.into_iter()).collect();
+        let encodings = options
+            .backend
+            .encoder
+            .encode(&image, &located, options.jitters);
+
+        for ((face, (_, landmarks)), measurements) in
+            faces.iter().zip(located.iter()).zip(encodings.iter())
+        {
+            Face::update_encoding(&face.uuid, measurements, landmarks, &model);
+            summary.reencoded += 1;
+        }
+    }
+    summary
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Faces re-encoded: {} (failed: {})",
+            self.reencoded, self.failed
+        )
+    }
+}
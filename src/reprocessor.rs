@@ -0,0 +1,225 @@
+use colored::Colorize;
+use dlib_face_recognition::Rectangle;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+use crate::db::face::Face;
+use crate::db::photo::Photo;
+use crate::detection;
+use crate::detection::options::Options;
+use crate::recognition;
+use crate::recognition::verdict::Verdict;
+use crate::storage;
+
+// a new face takes over a stored one when their boxes overlap at least this much (IoU)
+pub const MIN_OVERLAP: f64 = 0.5;
+
+pub enum Selection {
+    All,
+    // never processed, failed, without faces or processed by another detector
+    NeedingDetection,
+    Photos(Vec<String>),
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub photos: usize,
+    pub failed: usize,
+    // stored faces found again, they keep uuid and moderation
+    pub matched: usize,
+    pub added: usize,
+    // stored unmoderated faces which are not found anymore, faces drawn by hand are kept
+    pub removed: usize,
+}
+
+impl Selection {
+    // "all", "needed" or "uuid_1,uuid_2"
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "all" => Self::All,
+            "needed" => Self::NeedingDetection,
+            _ => Self::Photos(
+                value
+                    .split(',')
+                    .map(|uuid| uuid.trim().to_string())
+                    .collect(),
+            ),
+        }
+    }
+
+    fn photos(&self, options: &Options) -> Vec<Photo> {
+        match self {
+            Self::All => Photo::all(),
+            Self::NeedingDetection => Photo::needing_detection(&options.backend.detector.name()),
+            Self::Photos(uuids) => uuids.iter().map(|uuid| Photo::find(uuid)).collect(),
+        }
+    }
+}
+
+impl Summary {
+    fn add(&mut self, other: Summary) {
+        self.photos += other.photos;
+        self.failed += other.failed;
+        self.matched += other.matched;
+        self.added += other.added;
+        self.removed += other.removed;
+    }
+}
+
+pub fn call(selection: &Selection) -> Summary {
+    call_with_options(selection, &Options::library())
+}
+
+pub fn call_with_options(selection: &Selection, options: &Options) -> Summary {
+    let recognition_options = recognition::options::Options::from_env();
+    let mut summary = Summary::default();
+
+    for photo in selection.photos(options) {
+        println!("{}", photo.uuid.yellow());
+
+        match reprocess(&photo, options, &recognition_options) {
+            Err(error) => {
+                println!("{} {:?}", "Reprocessing failed:".red(), error);
+                Photo::save_detection_error(
+                    &photo.uuid,
+                    &options.backend.detector.name(),
+                    &options.parameters(),
+                    &error.to_string(),
+                );
+                summary.add(Summary {
+                    photos: 1,
+                    failed: 1,
+                    ..Default::default()
+                });
+            }
+            Ok(photo_summary) => summary.add(photo_summary),
+        }
+    }
+    summary
+}
+
+// re-runs detection over the stored original and reconciles found faces with stored ones.
+// Unmoderated faces are recognized again with their new measurements. Moderated and
+// annotated faces are kept even if the detector doesn't find them
+pub fn reprocess(
+    photo: &Photo,
+    options: &Options,
+    recognition_options: &recognition::options::Options,
+) -> Result<Summary, Box<dyn Error>> {
+    let folder_path = format!("{}/{}/", storage::IMAGES_DIR, photo.uuid);
+    let image = storage::open_original(&photo.uuid, &photo.original_file_name())?;
+    let mut detected = detection::analyze(image, options);
+
+    let stored = photo.faces();
+    let moderated: Vec<String> = Face::where_all(&format!(
+        "photo_uuid = '{}' AND moderated = 'true'",
+        photo.uuid
+    ))
+    .into_iter()
+    .map(|face| face.uuid)
+    .collect();
+    let annotated: Vec<&String> = stored
+        .iter()
+        .filter(|face| face.annotated)
+        .map(|face| &face.uuid)
+        .collect();
+
+    let pairs = reconcile(
+        &stored
+            .iter()
+            .map(|face| face.location())
+            .collect::<Vec<_>>(),
+        &detected
+            .faces
+            .iter()
+            .map(|face| face.face_location.unwrap())
+            .collect::<Vec<_>>(),
+        MIN_OVERLAP,
+    );
+
+    let mut summary = Summary {
+        photos: 1,
+        ..Default::default()
+    };
+
+    for (face, pair) in detected.faces.iter_mut().zip(pairs.iter()) {
+        match pair {
+            Some(index) => {
+                face.uuid = stored[*index].uuid.clone();
+                Face::update_detection(face);
+                summary.matched += 1;
+            }
+            None => {
+                Face::create(&photo.uuid, face);
+                summary.added += 1;
+            }
+        }
+        if !moderated.contains(&face.uuid) {
+            recognize(&face.uuid, recognition_options);
+        }
+    }
+
+    for (index, face) in stored.iter().enumerate() {
+        if pairs.contains(&Some(index))
+            || moderated.contains(&face.uuid)
+            || annotated.contains(&&face.uuid)
+        {
+            continue;
+        }
+        Face::delete(&face.uuid);
+        fs::remove_file(format!("{}{}.jpg", folder_path, face.uuid)).ok();
+        summary.removed += 1;
+    }
+
+    detection::store_crops(&photo.uuid, &detected, options.crop_margin);
+    Photo::save_detection_run(&photo.uuid, &detected);
+    Photo::update_face_count(&photo.uuid);
+
+    Ok(summary)
+}
+
+fn recognize(face_uuid: &String, options: &recognition::options::Options) {
+    let recognition = recognition::find_matches_with_options(face_uuid, options);
+    Face::update_verdict(face_uuid, &recognition.verdict, recognition.threshold);
+    if let Verdict::Known(person, _) = &recognition.verdict {
+        Face::update_person_uuid(face_uuid, &person.uuid);
+    }
+}
+
+// index of the stored box taken over by every detected box. Pairs are picked by the highest
+// overlap first and each stored box is taken once
+pub fn reconcile(
+    stored: &[Rectangle],
+    detected: &[Rectangle],
+    min_overlap: f64,
+) -> Vec<Option<usize>> {
+    let mut candidates: Vec<(usize, usize, f64)> = Vec::new();
+    for (detected_index, detected_location) in detected.iter().enumerate() {
+        for (stored_index, stored_location) in stored.iter().enumerate() {
+            let overlap = detection::nms::iou(detected_location, stored_location);
+            if overlap >= min_overlap {
+                candidates.push((detected_index, stored_index, overlap));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let mut result = vec![None; detected.len()];
+    for (detected_index, stored_index, _) in candidates {
+        if result[detected_index].is_none() && !result.contains(&Some(stored_index)) {
+            result[detected_index] = Some(stored_index);
+        }
+    }
+    result
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Photos reprocessed: {} (failed: {}), faces matched: {}, added: {}, removed: {}",
+            self.photos, self.failed, self.matched, self.added, self.removed
+        )
+    }
+}
@@ -17,10 +17,42 @@ pub struct AreaDimensions {
     pub height: u32,
 }
 
-pub fn save_original_image(folder_path: &String, image: &DynamicImage) -> Result<(), ImageError> {
-    match fs::create_dir_all(folder_path) {
-        Err(error) => panic!("Storage folder cannot be created: {:?}", error),
-        Ok(_) => image.save(format!("{}/original.jpg", folder_path)),
+pub fn original_file_name(format: ImageFormat) -> String {
+    format!("original.{}", crate::media::extension(format))
+}
+
+// stored originals are upright already, EXIF orientation is not applied again
+pub fn open_original(photo_uuid: &String, file_name: &String) -> ImageResult<DynamicImage> {
+    crate::media::open(&format!("{}/{}/{}", IMAGES_DIR, photo_uuid, file_name))
+}
+
+// copies `source` file as is when given, otherwise encodes the image in its original format,
+// or in PNG when the format can't be written. Returns format of the saved file.
+pub fn save_original_image(
+    folder_path: &String,
+    image: &DynamicImage,
+    source: Option<&String>,
+    format: ImageFormat,
+) -> Result<ImageFormat, ImageError> {
+    if let Err(error) = fs::create_dir_all(folder_path) {
+        panic!("Storage folder cannot be created: {:?}", error)
+    }
+
+    let path = |format| format!("{}/{}", folder_path, original_file_name(format));
+
+    if let Some(source) = source {
+        return match fs::copy(source, path(format)) {
+            Err(error) => Err(ImageError::IoError(error)),
+            Ok(_) => Ok(format),
+        };
+    }
+
+    match image.save_with_format(path(format), format) {
+        Err(ImageError::Unsupported(_)) => image
+            .save_with_format(path(ImageFormat::Png), ImageFormat::Png)
+            .map(|_| ImageFormat::Png),
+        Err(error) => Err(error),
+        Ok(_) => Ok(format),
     }
 }
 
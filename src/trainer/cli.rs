@@ -1,8 +1,9 @@
 use colored::Colorize;
-use debug_print::debug_println;
+use debug_print::debug_eprintln;
 use std::io;
 use uuid::Uuid;
 
+use crate::detection::options::Options;
 use crate::image_processor;
 
 pub fn cli() {
@@ -17,28 +18,38 @@ pub fn cli() {
             .to_string();
 
         let path = path.replace('\n', "");
-        let recognition_results = match image_processor::recognize_faces(&path) {
+        let recognition_results = match image_processor::recognize_faces_with_options(&path, &Options::enrolment()) {
                             Ok(result) => result.face_matches,
                             Err(error) => panic!("Recognition failed: {:?}", error)
                         };
 
         for (face_uuid, &ref recognition) in recognition_results.iter() {
-            let mut person_options: Vec<&crate::db::person::Person> = Vec::new();
-            for mtch in recognition.matches.iter() {
-                match &mtch.0 {
-                    None => {}
-                    Some(person) => {
-                        if !person_options.contains(&person) {
-                            person_options.push(person)
-                        }
-                    }
-                }
-            }
+            let face = crate::db::face::Face::find(&face_uuid);
+            println!(
+                "{} {} (confidence: {}, quality: {})",
+                "face".bold(),
+                face.uuid,
+                crate::detection::format_score(face.confidence),
+                crate::detection::format_score(face.quality)
+            );
+            println!("{}", recognition.verdict);
+
+            let person_options: Vec<&crate::db::person::Person> = recognition
+                .candidates
+                .iter()
+                .map(|candidate| &candidate.person)
+                .collect();
 
             if person_options.len() > 0 {
                 println!("{}", "is it?".bold());
-                for (i, person) in person_options.iter().enumerate() {
-                    println!("{}) {}", &i, &person.name);
+                for (i, candidate) in recognition.candidates.iter().enumerate() {
+                    println!(
+                        "{}) {} (distance: {}, margin: {})",
+                        &i,
+                        &candidate.person.name,
+                        crate::detection::format_score(Some(candidate.distance)),
+                        crate::detection::format_score(candidate.margin)
+                    );
                 }
                 println!("{}", "Enter number of one of the options".bold());
                 println!("{}", "or".bold());
@@ -53,7 +64,7 @@ pub fn cli() {
             let recognized_person_uuid = match answer.trim().parse::<usize>() {
                 Ok(num) => person_options[num].uuid.clone(),
                 Err(_) => {
-                    debug_println!("create new person");
+                    debug_eprintln!("create new person");
 
                     let name = answer.to_string().replace('\n', "");
 
@@ -1,16 +1,20 @@
 use colored::Colorize;
 use regex::Regex;
 use std::collections::HashMap;
+use std::error::Error;
 use std::fs;
 use std::path::Path;
 use uuid::Uuid;
 
 use crate::detection;
+use crate::detection::options::Options;
 use crate::detection::photo::Photo;
+use crate::media;
 
 pub struct DirectoryTrainer {
     pub dir: String,
     pub people: HashMap<String, String>,
+    pub options: Options,
 }
 
 impl DirectoryTrainer {
@@ -18,6 +22,7 @@ impl DirectoryTrainer {
         Self {
             dir: dir,
             people: HashMap::new(),
+            options: Options::enrolment(),
         }
     }
 
@@ -30,7 +35,7 @@ impl DirectoryTrainer {
         }
     }
 
-    fn files_list(dir: &Path) -> Vec<String> {
+    pub fn files_list(dir: &Path) -> Vec<String> {
         let mut result = Vec::new();
 
         if dir.is_dir() {
@@ -50,8 +55,22 @@ impl DirectoryTrainer {
     fn process_photo(&mut self, file_path: &String) {
         println!("{}", file_path.yellow());
 
+        let analyzed = match media::sniff(file_path) {
+            media::Kind::Image(_) => self.analyze_photo(file_path),
+            media::Kind::AnimatedGif => self.analyze_animation(file_path),
+            media::Kind::Unsupported => {
+                println!("{}", "Unsupported file, skipped".red());
+                return;
+            }
+        };
+        let photo = match analyzed {
+            Err(error) => {
+                println!("{} {}", "Skipped:".red(), error);
+                return;
+            }
+            Ok(photo) => photo,
+        };
         let name = Self::parse_name(&file_path);
-        let photo = Self::analyze_photo(file_path);
 
         crate::db::photo::Photo::save_detection_result(&photo);
 
@@ -60,7 +79,7 @@ impl DirectoryTrainer {
         crate::db::face::Face::moderate_person(&face_uuid, &recognized_person_uuid);
     }
 
-    fn parse_name(file_path: &String) -> String {
+    pub fn parse_name(file_path: &String) -> String {
         let caps = Self::person_name_regex().captures(file_path).unwrap();
         match caps.get(1) {
             Some(mtch) => mtch.as_str().to_string(),
@@ -73,13 +92,34 @@ impl DirectoryTrainer {
         Regex::new(r"(?:.*\/)?([a-z_]+)\/.*$").unwrap()
     }
 
-    fn analyze_photo(file_path: &String) -> Photo {
-        match detection::call(&file_path) {
-            Err(error) => panic!("Face detection failed: {:?}", error),
-            Ok(photo) => photo,
+    fn analyze_photo(&self, file_path: &String) -> Result<Photo, Box<dyn Error>> {
+        let photo = detection::call_with_options(&file_path, &self.options)?;
+        match photo.faces.is_empty() {
+            true => Err("No face found".into()),
+            false => Ok(photo),
         }
     }
 
+    // enrols the first frame with a face, frames are only analyzed until then
+    fn analyze_animation(&self, file_path: &String) -> Result<Photo, Box<dyn Error>> {
+        let mut result = None;
+        crate::video_processor::each_frame(file_path, |path_to_frame| {
+            let Ok(image) = media::open(path_to_frame) else { return false };
+            let photo = detection::analyze(image, &self.options);
+            let found = !photo.faces.is_empty();
+            if found {
+                result = Some(photo);
+            }
+            found
+        })?;
+
+        let Some(mut photo) = result else {
+            return Err("No face found in animation".into());
+        };
+        detection::store_analyzed(&mut photo, &self.options);
+        Ok(photo)
+    }
+
     fn get_person_uuid(&mut self, name: String) -> String {
         match self.people.contains_key(&name) {
             true => self.people.get(&name).unwrap().to_string(),
@@ -1,7 +1,9 @@
+use std::collections::HashMap;
 use std::sync::mpsc;
 use std::thread;
 use uuid::Uuid;
 
+use dlib_face_recognition::Rectangle;
 use eframe::egui;
 use egui::RichText;
 use egui_extras::image::RetainedImage;
@@ -39,9 +41,17 @@ impl UIPhoto {
 struct FaceFormData {
     uuid: String,
     texture: RetainedImage,
+    confidence: Option<f64>,
+    quality: Option<f64>,
+    verdict: String,
     selected_person_option: (String, String),
 }
 
+struct OutlierFormData {
+    outlier: crate::recognition::outliers::Outlier,
+    texture: RetainedImage,
+}
+
 // split into components
 // each should content its own data and allow to acess it by api
 // example PeopleList should contain vec<Person>, person_form, person_search. Clients should be able read only
@@ -51,6 +61,7 @@ struct VsUi {
     pub photos_rx: Option<mpsc::Receiver<UIPhoto>>,
     show_new_person_form: bool,
     show_recognition_form: bool,
+    show_outliers_form: bool,
     new_person_name: String,
     image_picked_path: String,
     recognition_result: Option<crate::image_processor::ProcessingResult>,
@@ -58,6 +69,16 @@ struct VsUi {
     current_photo_image: Option<RetainedImage>,
     faces_form_data: Vec<FaceFormData>,
     person_options: Vec<(String, String)>,
+    annotation_start: Option<egui::Pos2>,
+    // face drawn on the original photo, in its pixels
+    annotation: Option<Rectangle>,
+    // moderated faces likely assigned to the wrong person
+    outliers_form_data: Vec<OutlierFormData>,
+    // outliers are found in the background
+    reviewing: Option<thread::JoinHandle<Vec<OutlierFormData>>>,
+    // re-encoding runs in the background, recognition keeps working meanwhile
+    reencoding: Option<thread::JoinHandle<crate::reencoder::Summary>>,
+    reencoding_result: Option<String>,
 }
 
 impl Default for VsUi {
@@ -68,6 +89,7 @@ impl Default for VsUi {
             photos_rx: None,
             show_new_person_form: false,
             show_recognition_form: false,
+            show_outliers_form: false,
             new_person_name: String::from(""),
             image_picked_path: String::from(""),
             recognition_result: None,
@@ -75,6 +97,12 @@ impl Default for VsUi {
             current_photo_image: None,
             faces_form_data: Vec::new(),
             person_options: Vec::new(),
+            annotation_start: None,
+            annotation: None,
+            outliers_form_data: Vec::new(),
+            reviewing: None,
+            reencoding: None,
+            reencoding_result: None,
         }
     }
 }
@@ -83,12 +111,15 @@ impl eframe::App for VsUi {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         ctx.request_repaint();
         self.preload_photos();
+        self.finish_review();
+        self.finish_reencoding();
 
         self.top_panel(ctx);
         self.photos_list(ctx);
         self.new_person_form(ctx);
         self.recognition_form(ctx);
         self.photo_form(ctx);
+        self.outliers_form(ctx);
     }
 }
 
@@ -135,6 +166,57 @@ impl VsUi {
         }
     }
 
+    fn fetch_outliers() -> Vec<OutlierFormData> {
+        let outliers = crate::recognition::outliers::call();
+        let uuids: Vec<String> = outliers
+            .iter()
+            .map(|outlier| format!("'{}'", outlier.face_uuid))
+            .collect();
+        let photo_uuids: HashMap<String, String> =
+            crate::db::face::Face::where_all(&format!("uuid IN({})", uuids.join(", ")))
+                .into_iter()
+                .map(|face| (face.uuid, face.photo_uuid))
+                .collect();
+
+        outliers
+            .into_iter()
+            .map(|outlier| OutlierFormData {
+                texture: storage::read_image_for_ui(
+                    &photo_uuids[&outlier.face_uuid],
+                    format!("{}.jpg", outlier.face_uuid).as_str(),
+                ),
+                outlier: outlier,
+            })
+            .collect()
+    }
+
+    fn finish_review(&mut self) {
+        if !self.reviewing.as_ref().map_or(false, |handle| handle.is_finished()) {
+            return;
+        }
+
+        let handle = self.reviewing.take().unwrap();
+        match handle.join() {
+            Err(_) => println!("Review failed"),
+            Ok(outliers_form_data) => {
+                self.outliers_form_data = outliers_form_data;
+                self.show_outliers_form = true;
+            }
+        }
+    }
+
+    fn finish_reencoding(&mut self) {
+        if !self.reencoding.as_ref().map_or(false, |handle| handle.is_finished()) {
+            return;
+        }
+
+        let handle = self.reencoding.take().unwrap();
+        self.reencoding_result = Some(match handle.join() {
+            Ok(summary) => summary.to_string(),
+            Err(_) => String::from("Re-encoding failed"),
+        });
+    }
+
     fn person_by_uuid(&self, uuid: &String) -> Option<&crate::db::person::Person> {
         self.people.iter().find(|person| &person.uuid == uuid)
     }
@@ -145,6 +227,20 @@ impl VsUi {
             egui::menu::bar(ui, |ui| {
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
                     let recognize_btn = ui.add(egui::Button::new("Recognize"));
+                    let review_btn = ui.add_enabled(
+                        self.reviewing.is_none(),
+                        egui::Button::new(match self.reviewing {
+                            Some(_) => "Reviewing...",
+                            None => "Review",
+                        }),
+                    );
+                    let reencode_btn = ui.add_enabled(
+                        self.reencoding.is_none(),
+                        egui::Button::new(match self.reencoding {
+                            Some(_) => "Re-encoding...",
+                            None => "Re-encode",
+                        }),
+                    );
                     let add_person_button = ui.add(egui::Button::new("Add Person"));
 
                     if add_person_button.clicked() {
@@ -153,6 +249,16 @@ impl VsUi {
                     if recognize_btn.clicked() {
                         self.show_recognition_form = true;
                     }
+                    if review_btn.clicked() {
+                        self.reviewing = Some(thread::spawn(Self::fetch_outliers));
+                    }
+                    if reencode_btn.clicked() {
+                        self.reencoding_result = None;
+                        self.reencoding = Some(crate::reencoder::spawn());
+                    }
+                    if let Some(result) = &self.reencoding_result {
+                        ui.label(result);
+                    }
                 });
             });
             ui.add_space(10.);
@@ -167,7 +273,10 @@ impl VsUi {
                         let button = self.view_photo_list_item(ctx, ui, photo);
                         if button.clicked() {
                             self.current_photo_image =
-                                Some(storage::read_image_for_ui(&photo.data.uuid, "original.jpg"));
+                                Some(storage::read_image_for_ui(
+                                &photo.data.uuid,
+                                &photo.data.original_file_name(),
+                            ));
 
                             for face in photo.faces.iter() {
                                 let selected_person_option = match face.person() {
@@ -181,9 +290,16 @@ impl VsUi {
                                         &photo.data.uuid,
                                         format!("{}.jpg", face.uuid).as_str(),
                                     ),
+                                    confidence: face.confidence,
+                                    quality: face.quality,
+                                    verdict: face.verdict().map_or(String::from("n/a"), |verdict| verdict.to_string()),
                                     selected_person_option: selected_person_option,
                                 });
                             }
+                            // best quality faces first
+                            self.faces_form_data.sort_by(|a, b| {
+                                b.quality.unwrap_or(-1.0).partial_cmp(&a.quality.unwrap_or(-1.0)).unwrap()
+                            });
 
                             for person in self.people.iter() {
                                 self.person_options.push((
@@ -255,6 +371,10 @@ impl VsUi {
                     for (name, coef) in result.display_data.iter() {
                         ui.label(&format!("{} -> {}", name, coef));
                     }
+                    ui.label(RichText::new("Verdicts:").strong());
+                    for recognition in result.face_matches.values() {
+                        ui.label(recognition.verdict.to_string());
+                    }
                     let ui_photo = self
                         .photos
                         .iter()
@@ -290,6 +410,59 @@ impl VsUi {
             });
     }
 
+    fn outliers_form(&mut self, ctx: &egui::Context) {
+        if !self.show_outliers_form {
+            return;
+        }
+        egui::Window::new("Review moderation")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let mut reassigned = None;
+
+                egui::ScrollArea::vertical()
+                    .max_height(500.)
+                    .show(ui, |ui| {
+                        if self.outliers_form_data.is_empty() {
+                            ui.label("no suspicious faces");
+                        }
+                        for (i, outlier_data) in self.outliers_form_data.iter().enumerate() {
+                            let outlier = &outlier_data.outlier;
+                            ui.horizontal(|ui| {
+                                ui.add(sized_img_element(&ctx, &outlier_data.texture, 100, 100));
+
+                                ui.label(format!(
+                                    "{}: {} (threshold: {:.2})",
+                                    outlier.person.name,
+                                    crate::detection::format_score(outlier.distance),
+                                    outlier.threshold
+                                ));
+                                if let Some(person) = &outlier.suggested_person {
+                                    ui.label(format!(
+                                        "{}: {}",
+                                        person.name,
+                                        crate::detection::format_score(outlier.suggested_distance)
+                                    ));
+                                    if ui.button(format!("Move to {}", person.name)).clicked() {
+                                        crate::db::face::Face::moderate_person(&outlier.face_uuid, &person.uuid);
+                                        reassigned = Some(i);
+                                    }
+                                }
+                            });
+                        }
+                    });
+
+                if let Some(i) = reassigned {
+                    self.outliers_form_data.remove(i);
+                }
+
+                if ui.button("Close").clicked() {
+                    self.outliers_form_data = Vec::new();
+                    self.show_outliers_form = false;
+                }
+            });
+    }
+
     fn view_photo_list_item(
         &self,
         ctx: &egui::Context,
@@ -311,10 +484,15 @@ impl VsUi {
     }
 
     fn photo_form(&mut self, ctx: &egui::Context) {
-        let _photo_uuid = match &self.edit_photo_uuid {
+        let photo_uuid = match &self.edit_photo_uuid {
             Some(uuid) => uuid.clone(),
             None => return,
         };
+        let detection_summary = self
+            .photos
+            .iter()
+            .find(|ui_photo| ui_photo.data.uuid == photo_uuid)
+            .map_or(String::from(""), |ui_photo| detection_summary(&ui_photo.data));
 
         egui::Window::new("View photo")
             .collapsible(false)
@@ -324,12 +502,67 @@ impl VsUi {
                     .max_height(500.)
                     .show(ui, |ui| {
                         let Some(texture) = &self.current_photo_image else { panic!("Original photo texture is missing") };
-                        ui.add(sized_img_element(&ctx, texture, 540, 350));
+                        let photo_element = ui.add(
+                            sized_img_element(&ctx, texture, 540, 350).sense(egui::Sense::drag()),
+                        );
+                        ui.label(&detection_summary);
+
+                        // missed face can be drawn on the photo
+                        if photo_element.drag_started() {
+                            self.annotation_start = photo_element.interact_pointer_pos();
+                        }
+                        if let (true, Some(start), Some(end)) = (
+                            photo_element.dragged(),
+                            self.annotation_start,
+                            photo_element.interact_pointer_pos(),
+                        ) {
+                            self.annotation = Some(to_image_area(
+                                photo_element.rect,
+                                egui::Rect::from_two_pos(start, end),
+                                texture.size(),
+                            ));
+                        }
+                        if let Some(annotation) = &self.annotation {
+                            ui.painter().rect_stroke(
+                                to_screen_rect(photo_element.rect, annotation, texture.size()),
+                                0.0,
+                                egui::Stroke::new(2.0, egui::Color32::RED),
+                            );
+                        }
+                        if self.annotation.is_some() && ui.button("Add face").clicked() {
+                            match crate::image_processor::annotate_face(&photo_uuid, self.annotation.unwrap()) {
+                                Err(error) => println!("Face cannot be added: {:?}", error),
+                                Ok(face) => {
+                                    self.faces_form_data.push(FaceFormData {
+                                        texture: storage::read_image_for_ui(
+                                            &photo_uuid,
+                                            format!("{}.jpg", face.uuid).as_str(),
+                                        ),
+                                        uuid: face.uuid,
+                                        confidence: face.confidence,
+                                        quality: face.quality,
+                                        verdict: String::from("n/a"),
+                                        selected_person_option: (String::from(""), String::from("")),
+                                    });
+                                }
+                            }
+                            self.annotation_start = None;
+                            self.annotation = None;
+                        }
 
                         for face_data in self.faces_form_data.iter_mut() {
                             ui.horizontal(|ui| {
                                 ui.add(sized_img_element(&ctx, &face_data.texture, 100, 100));
 
+                                ui.label(format!(
+                                    "Confidence: {}",
+                                    crate::detection::format_score(face_data.confidence)
+                                ));
+                                ui.label(format!(
+                                    "Quality: {}",
+                                    crate::detection::format_score(face_data.quality)
+                                ));
+                                ui.label(format!("Verdict: {}", face_data.verdict));
                                 ui.label("Person:");
 
                                 let mut selected_uuid = &face_data.selected_person_option.0;
@@ -361,10 +594,17 @@ impl VsUi {
 
                 ui.horizontal(|ui| {
                     if ui.button("Cancel").clicked() {
+                        if let Some(ui_photo) = self.photos.iter_mut().find(|ui_photo| ui_photo.data.uuid == photo_uuid) {
+                            // manually added faces are saved already
+                            ui_photo.set_faces(ui_photo.data.faces());
+                        }
+
                         self.edit_photo_uuid = None;
                         self.current_photo_image = None;
                         self.person_options = Vec::new();
                         self.faces_form_data = Vec::new();
+                        self.annotation_start = None;
+                        self.annotation = None;
                     }
 
                     if ui.button("Save").clicked() {
@@ -383,6 +623,8 @@ impl VsUi {
                         self.current_photo_image = None;
                         self.person_options = Vec::new();
                         self.faces_form_data = Vec::new();
+                        self.annotation_start = None;
+                        self.annotation = None;
                     }
                 });
 
@@ -409,3 +651,46 @@ pub fn sized_img_element(
         [dimensions.width as f32, dimensions.height as f32],
     )
 }
+
+// `selection` on the displayed image to pixels of the original
+fn to_image_area(displayed: egui::Rect, selection: egui::Rect, original_size: [usize; 2]) -> Rectangle {
+    let selection = selection.intersect(displayed);
+    let scale_x = original_size[0] as f32 / displayed.width();
+    let scale_y = original_size[1] as f32 / displayed.height();
+
+    Rectangle {
+        left: ((selection.min.x - displayed.min.x) * scale_x).round() as i64,
+        top: ((selection.min.y - displayed.min.y) * scale_y).round() as i64,
+        right: ((selection.max.x - displayed.min.x) * scale_x).round() as i64,
+        bottom: ((selection.max.y - displayed.min.y) * scale_y).round() as i64,
+    }
+}
+
+fn to_screen_rect(displayed: egui::Rect, area: &Rectangle, original_size: [usize; 2]) -> egui::Rect {
+    let scale_x = displayed.width() / original_size[0] as f32;
+    let scale_y = displayed.height() / original_size[1] as f32;
+
+    egui::Rect::from_min_max(
+        egui::pos2(
+            displayed.min.x + area.left as f32 * scale_x,
+            displayed.min.y + area.top as f32 * scale_y,
+        ),
+        egui::pos2(
+            displayed.min.x + area.right as f32 * scale_x,
+            displayed.min.y + area.bottom as f32 * scale_y,
+        ),
+    )
+}
+
+fn detection_summary(photo: &Photo) -> String {
+    match photo.status() {
+        None => String::from("Detection: not recorded"),
+        Some(status) => format!(
+            "Detection: {}, {} faces by {} in {}s",
+            status.name(),
+            photo.face_count,
+            photo.detector,
+            crate::detection::format_score(photo.detection_duration)
+        ),
+    }
+}
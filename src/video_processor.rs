@@ -7,77 +7,92 @@ use ffmpeg::util::frame::video::Video;
 use std::fs;
 use image;
 use uuid::Uuid;
+use crate::detection::options::Options;
 use crate::storage;
 
 // WIP
 pub fn call(path_to_file: &String) -> Result<(), ffmpeg::Error> {
-    ffmpeg::init().unwrap();
-
-    if let Ok(mut ictx) = input(path_to_file) {
-        let tmp_folder = create_tmp_dir();
-
-        let input = ictx
-            .streams()
-            .best(Type::Video)
-            .ok_or(ffmpeg::Error::StreamNotFound)?;
-        let video_stream_index = input.index();
-
-        let context_decoder = ffmpeg::codec::context::Context::from_parameters(input.parameters())?;
-        let mut decoder = context_decoder.decoder().video()?;
-
-        let mut scaler = Context::get(
-            decoder.format(),
-            decoder.width(),
-            decoder.height(),
-            Pixel::RGB24,
-            decoder.width(),
-            decoder.height(),
-            Flags::BILINEAR,
-        )?;
-
-        let mut frame_index = 0;
-
-        let mut receive_and_process_decoded_frames =
-            |decoder: &mut ffmpeg::decoder::Video| -> Result<bool, ffmpeg::Error> {
-                let mut decoded = Video::empty();
-                while decoder.receive_frame(&mut decoded).is_ok() {
-                    let mut rgb_frame = Video::empty();
-                    scaler.run(&decoded, &mut rgb_frame)?;
-                    let path_to_image = save_image(&rgb_frame, frame_index, &tmp_folder).unwrap();
-
-                    match crate::image_processor::call(&path_to_image) {
-                        None => {},
-                        Some(recognition_result) => {
-                            if recognition_result.is_face_found() { 
-                                return Ok(true) 
-                            } else {
-                                return Ok(false)
-                            }
-                        }
-                    }
-
-                    frame_index += 1;
+    let options = Options::realtime();
+
+    each_frame(path_to_file, |path_to_image| {
+        match crate::image_processor::call_with_options(path_to_image, &options) {
+            None => false,
+            Some(recognition_result) => {
+                if recognition_result.is_face_found() {
+                    println!("{:?}", recognition_result.display_data);
                 }
-                Ok(false)
-            };
-
-        for (stream, packet) in ictx.packets() {
-            if stream.index() == video_stream_index {
-                decoder.send_packet(&packet)?;
-                match receive_and_process_decoded_frames(&mut decoder) {
-                    Ok(true) => break,
-                    Err(_) => todo!(),
-                    Ok(false) => {},
-                };
+                recognition_result.is_face_found()
             }
         }
-        decoder.send_eof()?;
-        // receive_and_process_decoded_frames(&mut decoder)?;
+    })
 
-        fs::remove_dir_all(tmp_folder).unwrap();
+    // TODO: return recognition result
+}
+
+// saves decoded frames of the best video stream as temporary jpg files and passes their paths
+// to `process` until it returns true. Also reads animated gifs.
+pub fn each_frame<F: FnMut(&String) -> bool>(
+    path_to_file: &String,
+    mut process: F,
+) -> Result<(), ffmpeg::Error> {
+    ffmpeg::init()?;
+
+    let mut ictx = input(path_to_file)?;
+    let tmp_folder = TmpDir::create();
+    let tmp_folder = &tmp_folder.path;
+
+    let input = ictx
+        .streams()
+        .best(Type::Video)
+        .ok_or(ffmpeg::Error::StreamNotFound)?;
+    let video_stream_index = input.index();
+
+    let context_decoder = ffmpeg::codec::context::Context::from_parameters(input.parameters())?;
+    let mut decoder = context_decoder.decoder().video()?;
+
+    let mut scaler = Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        Pixel::RGB24,
+        decoder.width(),
+        decoder.height(),
+        Flags::BILINEAR,
+    )?;
+
+    let mut frame_index = 0;
+
+    let mut receive_and_process_decoded_frames =
+        |decoder: &mut ffmpeg::decoder::Video| -> Result<bool, ffmpeg::Error> {
+            let mut decoded = Video::empty();
+            while decoder.receive_frame(&mut decoded).is_ok() {
+                let mut rgb_frame = Video::empty();
+                scaler.run(&decoded, &mut rgb_frame)?;
+                let path_to_image = save_image(&rgb_frame, frame_index, tmp_folder).unwrap();
+                frame_index += 1;
+
+                if process(&path_to_image) {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        };
+
+    let mut done = false;
+    for (stream, packet) in ictx.packets() {
+        if stream.index() == video_stream_index {
+            decoder.send_packet(&packet)?;
+            if receive_and_process_decoded_frames(&mut decoder)? {
+                done = true;
+                break;
+            }
+        }
+    }
+    if !done {
+        decoder.send_eof()?;
+        receive_and_process_decoded_frames(&mut decoder)?;
     }
 
-    // TODO: return recognition result
     Ok(())
 }
 
@@ -93,10 +108,25 @@ fn save_image(frame: &Video, index: usize, tmp_folder: &String) -> std::result::
     Ok(path_to_file)
 }
 
-fn create_tmp_dir() -> String {
-    let folder_path = format!("{}/video/{}/", storage::TMP_DIR, Uuid::new_v4().to_string());
-    match fs::create_dir_all(&folder_path) {
-        Err(error) => panic!("Tmp folder cannot be created: {:?}", error),
-        Ok(_) => folder_path,
+// folder of extracted frames, removed however processing ends
+struct TmpDir {
+    path: String,
+}
+
+impl TmpDir {
+    fn create() -> Self {
+        let folder_path = format!("{}/video/{}/", storage::TMP_DIR, Uuid::new_v4().to_string());
+        match fs::create_dir_all(&folder_path) {
+            Err(error) => panic!("Tmp folder cannot be created: {:?}", error),
+            Ok(_) => Self { path: folder_path },
+        }
+    }
+}
+
+impl Drop for TmpDir {
+    fn drop(&mut self) {
+        if let Err(error) = fs::remove_dir_all(&self.path) {
+            eprintln!("Tmp folder cannot be removed: {:?}", error);
+        }
     }
 }
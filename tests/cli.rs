@@ -0,0 +1,63 @@
+mod common;
+
+use std::fs;
+use std::process::{Command, Output};
+
+// runs the binary against the test database set up by `common::setup`
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_video_sentry"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+// the whole stdout is one JSON document, diagnostics go to stderr
+fn json(args: &[&str]) -> serde_json::Value {
+    let output = run(args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn test_processor_json() {
+    common::setup();
+    common::pretrain();
+
+    let result = json(&["processor", "tests/fixtures/people/marion/2.jpg", "--json"]);
+    assert_eq!(result["display_data"][0][0], "marion");
+
+    fs::remove_dir_all(format!("storage/images/{}", result["photo"]["uuid"].as_str().unwrap())).unwrap();
+    common::cleanup();
+}
+
+#[test]
+fn test_verify_json() {
+    common::setup();
+
+    let detected = common::detect(&common::fake_options("verified_cli@1", vec![0.1, 0.12]));
+    let result = json(&["verify", &detected.faces[0].uuid, &detected.faces[1].uuid, "--json"]);
+    assert_eq!(result["same_person"], true);
+    assert_eq!(result["left"]["input"], detected.faces[0].uuid);
+
+    common::remove_photos(&[&detected]);
+}
+
+#[test]
+fn test_usage() {
+    common::setup();
+
+    let output = run(&["unknown"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown command: unknown"));
+
+    let output = run(&["train"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Usage: cargo run train path_to_dir"));
+
+    let output = run(&["threshold", "camera", "front door", "0.5"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown threshold target: camera"));
+
+    common::cleanup();
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use uuid::Uuid;
+use video_sentry::clusterer;
+use video_sentry::clusterer::options::Options;
+use video_sentry::db::cluster::Cluster;
+use video_sentry::db::face::Face;
+
+#[test]
+fn test_chinese_whispers() {
+    let embeddings = vec![
+        vec![0.0, 0.0],
+        vec![0.1, 0.0],
+        vec![0.2, 0.0],
+        vec![5.0, 5.0],
+        vec![5.1, 5.0],
+        vec![9.0, 0.0],
+    ];
+    let refs: Vec<&Vec<f64>> = embeddings.iter().collect();
+
+    let labels = clusterer::chinese_whispers(&refs, 0.5, 100);
+    assert_eq!(labels[0], labels[1]);
+    assert_eq!(labels[1], labels[2]);
+    assert_eq!(labels[3], labels[4]);
+    assert_ne!(labels[0], labels[3]);
+    assert_ne!(labels[5], labels[0]);
+    assert_ne!(labels[5], labels[3]);
+
+    assert_eq!(clusterer::chinese_whispers(&[], 0.5, 100), Vec::<usize>::new());
+}
+
+#[test]
+fn test_cluster_and_assign() {
+    common::setup();
+
+    let detected = common::detect(&common::fake_options("clustered@1", vec![0.71, 0.711, 0.95]));
+
+    let summary = clusterer::call_with_options(&Options::default());
+    assert!(summary.faces >= 3);
+
+    let first = Face::find(&detected.faces[0].uuid);
+    assert!(!first.cluster_uuid.is_empty());
+    assert_eq!(Face::find(&detected.faces[1].uuid).cluster_uuid, first.cluster_uuid);
+    // a single face is not a cluster
+    assert!(Face::find(&detected.faces[2].uuid).cluster_uuid.is_empty());
+
+    let cluster = Cluster::find(&first.cluster_uuid);
+    assert_eq!(cluster.face_count, 2);
+    assert!(Cluster::all().contains(&cluster));
+
+    let name = format!("cluster {}", Uuid::new_v4());
+    let (person, assigned) = clusterer::assign(&cluster.uuid, &name);
+    assert_eq!(assigned, 2);
+    assert_eq!(person.name, name);
+    for face in detected.faces[..2].iter() {
+        let face = Face::find(&face.uuid);
+        assert_eq!(face.person_uuid, person.uuid);
+        assert!(face.cluster_uuid.is_empty());
+    }
+    assert!(!Cluster::all().contains(&cluster));
+
+    // moderated faces are not clustered again
+    clusterer::call_with_options(&Options::default());
+    assert!(Face::find(&detected.faces[0].uuid).cluster_uuid.is_empty());
+
+    // neither are faces recognized as a person
+    let recognized = common::detect(&common::fake_options("clustered@1", vec![0.81, 0.811]));
+    for face in recognized.faces.iter() {
+        Face::update(&face.uuid, vec![(&"person_uuid".to_string(), &person.uuid)]);
+    }
+    clusterer::call_with_options(&Options::default());
+    for face in recognized.faces.iter() {
+        assert!(Face::find(&face.uuid).cluster_uuid.is_empty());
+    }
+
+    common::remove_photos(&[&detected, &recognized]);
+}
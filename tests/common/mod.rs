@@ -1,19 +1,111 @@
+// every test binary compiles this module, but not every binary uses every helper
+#![allow(dead_code)]
+
+use dlib_face_recognition::Rectangle;
 use std::env;
 use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
 use video_sentry;
+use video_sentry::db::photo::Photo;
+use video_sentry::detection;
+use video_sentry::detection::backend::fake::Fake;
+use video_sentry::detection::backend::Backend;
+
+// tests of a binary run in parallel and share ./tmp/, so only the last one
+// to clean up removes it
+static RUNNING: Mutex<usize> = Mutex::new(0);
 
 // TODO: create db for each thread
 pub fn setup() {
+    *RUNNING.lock().unwrap() += 1;
     env::set_var("DATABASE_URL", "./tmp/test/storage/db/database.sql");
     env::set_var("DATABASE_FOLDER", "./tmp/test/storage/db/");
     video_sentry::db::init();
 }
 
-// TODO: remove tmp folder after all tests
 pub fn cleanup() {
-    fs::remove_dir_all("./tmp/").unwrap();
+    let mut running = RUNNING.lock().unwrap();
+    *running -= 1;
+    if *running == 0 && Path::new("./tmp/").exists() {
+        fs::remove_dir_all("./tmp/").unwrap();
+    }
 }
 
 pub fn pretrain(){
     video_sentry::trainer::directory_trainer::DirectoryTrainer::new("tests/fixtures/trainer".to_string()).call();
-}
\ No newline at end of file
+}
+
+// square box of `size` px
+pub fn rectangle(left: i64, top: i64, size: i64) -> Rectangle {
+    Rectangle {
+        left: left,
+        top: top,
+        right: left + size,
+        bottom: top + size,
+    }
+}
+
+// scripted face of the fake backend
+pub fn detection(location: Rectangle, confidence: Option<f64>) -> detection::Detection {
+    detection::Detection {
+        location: location,
+        confidence: confidence,
+    }
+}
+
+// 80px box of a scripted face, faces of one image are apart by `left`
+pub fn fake_detection(left: i64) -> detection::Detection {
+    detection(rectangle(left, 10, 80), None)
+}
+
+// fake backend finding `detections`, measured as `embeddings` by `model`. Faces
+// without an embedding get one derived from their box
+pub fn fake_backend(
+    model: &str,
+    detections: Vec<detection::Detection>,
+    embeddings: Vec<Vec<f64>>,
+) -> detection::options::Options {
+    detection::options::Options {
+        backend: Backend::fake(Fake::new(detections, embeddings).with_model(model)),
+        ..Default::default()
+    }
+}
+
+// fake backend finding faces at `locations`, with embeddings derived from their boxes
+pub fn fake_locations(locations: Vec<Rectangle>) -> detection::options::Options {
+    let detections = locations
+        .into_iter()
+        .map(|location| detection(location, None))
+        .collect();
+
+    fake_backend("fake@1", detections, vec![])
+}
+
+// fake backend finding a face per embedding value, measured as `[value; 128]` by `model`
+pub fn fake_options(model: &str, embeddings: Vec<f64>) -> detection::options::Options {
+    let detections = (0..embeddings.len())
+        .map(|i| fake_detection(10 + i as i64 * 200))
+        .collect();
+
+    fake_backend(
+        model,
+        detections,
+        embeddings.into_iter().map(|value| vec![value; 128]).collect(),
+    )
+}
+
+// detects faces of the fixture photo and stores them
+pub fn detect(options: &detection::options::Options) -> detection::photo::Photo {
+    let detected = detection::call_with_options(&"tests/fixtures/1.jpg".to_string(), options).unwrap();
+    Photo::save_detection_result(&detected);
+    detected
+}
+
+// removes images stored for `photos`, then cleans up
+pub fn remove_photos(photos: &[&detection::photo::Photo]) {
+    for photo in photos.iter() {
+        fs::remove_dir_all(format!("storage/images/{}", photo.uuid)).unwrap();
+    }
+    cleanup();
+}
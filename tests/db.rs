@@ -1,5 +1,8 @@
+mod common;
+
 #[cfg(test)]
 mod db_tests {
+    use crate::common;
     use uuid::Uuid;
     use video_sentry::db;
 
@@ -50,4 +53,159 @@ mod db_tests {
         assert_eq!(items[0].uuid, uuid_1);
         assert_eq!(items[1].uuid, uuid_2);
     }
+
+    #[test]
+    fn test_db_face_location_and_landmarks() {
+        db::init();
+
+        let photo_uuid = Uuid::new_v4().to_string();
+        let mut face_image = video_sentry::detection::face_image::FaceImage::new();
+        let location = dlib_face_recognition::Rectangle {
+            left: 12,
+            top: 156,
+            right: 91,
+            bottom: 235,
+        };
+        face_image.store_face_location(location);
+        face_image.store_measurements(vec![-0.03625381365418434, 0.14430001378059387]);
+        face_image.store_landmarks(vec![(20, 180), (25, 191)]);
+
+        let item = video_sentry::db::face::Face::create(&photo_uuid, &face_image);
+
+        assert_eq!(item.uuid, face_image.uuid);
+        assert_eq!(item.location(), location);
+        assert_eq!(item.deserialize_landmarks(), vec![(20, 180), (25, 191)]);
+        assert_eq!(item.deserialize_measurements().len(), 2);
+
+        video_sentry::db::face::Face::delete(&item.uuid);
+    }
+
+    #[test]
+    fn test_db_photo_detection_status() {
+        use video_sentry::detection::status::Status;
+
+        db::init();
+
+        let options = common::fake_locations(vec![common::rectangle(10, 10, 80)]);
+        let no_faces_options = common::fake_locations(vec![]);
+
+        let with_faces = video_sentry::detection::analyze(image::DynamicImage::new_rgb8(100, 100), &options);
+        let without_faces =
+            video_sentry::detection::analyze(image::DynamicImage::new_rgb8(100, 100), &no_faces_options);
+        video_sentry::db::photo::Photo::save_detection_result(&with_faces);
+        video_sentry::db::photo::Photo::save_detection_result(&without_faces);
+
+        let item = video_sentry::db::photo::Photo::find(&with_faces.uuid);
+        assert_eq!(item.status(), Some(Status::Ok));
+        assert_eq!(item.detector, "fake");
+        assert_eq!(item.face_count, 1);
+        assert!(item.detection_duration.is_some());
+        assert!(item.detection_parameters.contains("\"min_face_size\":40"));
+
+        let item = video_sentry::db::photo::Photo::find(&without_faces.uuid);
+        assert_eq!(item.status(), Some(Status::NoFaces));
+        assert_eq!(item.face_count, 0);
+
+        let needing_detection: Vec<String> = video_sentry::db::photo::Photo::needing_detection(&"fake".to_string())
+            .into_iter()
+            .map(|photo| photo.uuid)
+            .collect();
+        assert!(needing_detection.contains(&without_faces.uuid));
+        assert!(!needing_detection.contains(&with_faces.uuid));
+
+        video_sentry::db::photo::Photo::save_detection_error(
+            &with_faces.uuid,
+            &"fake".to_string(),
+            &options.parameters(),
+            &"original can't be read".to_string(),
+        );
+        let item = video_sentry::db::photo::Photo::find(&with_faces.uuid);
+        assert_eq!(item.status(), Some(Status::Error));
+        assert_eq!(item.detection_error, "original can't be read");
+
+        for face in item.faces() {
+            video_sentry::db::face::Face::delete(&face.uuid);
+        }
+        video_sentry::db::photo::Photo::delete(&with_faces.uuid);
+        video_sentry::db::photo::Photo::delete(&without_faces.uuid);
+    }
+
+    #[test]
+    fn test_db_face_verdict() {
+        use video_sentry::recognition::verdict::Verdict;
+
+        db::init();
+
+        let mut face_image = video_sentry::detection::face_image::FaceImage::new();
+        face_image.store_face_location(dlib_face_recognition::Rectangle {
+            left: 0,
+            top: 0,
+            right: 10,
+            bottom: 10,
+        });
+        let item = video_sentry::db::face::Face::create(&Uuid::new_v4().to_string(), &face_image);
+        assert_eq!(item.verdict(), None);
+
+        let person_1 = video_sentry::db::person::Person::create(&Uuid::new_v4().to_string(), &"verdict_1".to_string());
+        let person_2 = video_sentry::db::person::Person::create(&Uuid::new_v4().to_string(), &"verdict_2".to_string());
+
+        let ambiguous = Verdict::Ambiguous(vec![person_1.clone(), person_2.clone()]);
+        video_sentry::db::face::Face::update_verdict(&item.uuid, &ambiguous, Some(0.6));
+        assert_eq!(video_sentry::db::face::Face::find(&item.uuid).verdict(), Some(ambiguous));
+
+        video_sentry::db::face::Face::update_person_uuid(&item.uuid, &person_1.uuid);
+        video_sentry::db::face::Face::update_verdict(&item.uuid, &Verdict::Known(person_1.clone(), 0.75), Some(0.4));
+        let item = video_sentry::db::face::Face::find(&item.uuid);
+        assert_eq!(item.verdict(), Some(Verdict::Known(person_1.clone(), 0.75)));
+        assert_eq!(item.verdict_confidence, Some(0.75));
+        assert_eq!(item.threshold, Some(0.4));
+
+        video_sentry::db::face::Face::update_verdict(&item.uuid, &Verdict::Unknown, None);
+        assert_eq!(video_sentry::db::face::Face::find(&item.uuid).verdict(), Some(Verdict::Unknown));
+
+        video_sentry::db::face::Face::delete(&item.uuid);
+        video_sentry::db::person::Person::delete(&person_1.uuid);
+        video_sentry::db::person::Person::delete(&person_2.uuid);
+    }
+
+    #[test]
+    fn test_db_person_merge() {
+        db::init();
+
+        let mut face_image = video_sentry::detection::face_image::FaceImage::new();
+        face_image.store_face_location(dlib_face_recognition::Rectangle {
+            left: 0,
+            top: 0,
+            right: 10,
+            bottom: 10,
+        });
+        let item = video_sentry::db::face::Face::create(&Uuid::new_v4().to_string(), &face_image);
+
+        let person = video_sentry::db::person::Person::create(&Uuid::new_v4().to_string(), &"John Doe".to_string());
+        let other = video_sentry::db::person::Person::create(&Uuid::new_v4().to_string(), &"john".to_string());
+        video_sentry::db::face::Face::moderate_person(&item.uuid, &other.uuid);
+
+        let moved = video_sentry::db::person::Person::merge(&person.uuid, &other.uuid).unwrap();
+        assert_eq!(moved, 1);
+        assert_eq!(video_sentry::db::face::Face::find(&item.uuid).person_uuid, person.uuid);
+        assert!(video_sentry::db::person::Person::where_all(&format!("uuid = '{}'", other.uuid)).is_empty());
+
+        video_sentry::db::face::Face::delete(&item.uuid);
+        video_sentry::db::person::Person::delete(&person.uuid);
+    }
+
+    #[test]
+    fn test_db_person_merge_invalid() {
+        db::init();
+
+        let person = video_sentry::db::person::Person::create(&Uuid::new_v4().to_string(), &"Jane Doe".to_string());
+        let missing_uuid = Uuid::new_v4().to_string();
+
+        assert!(video_sentry::db::person::Person::merge(&person.uuid, &person.uuid).is_err());
+        assert!(video_sentry::db::person::Person::merge(&person.uuid, &missing_uuid).is_err());
+        assert!(video_sentry::db::person::Person::merge(&missing_uuid, &person.uuid).is_err());
+        assert!(video_sentry::db::person::Person::find_by_uuid(&person.uuid).is_some());
+
+        video_sentry::db::person::Person::delete(&person.uuid);
+    }
 }
@@ -1,11 +1,15 @@
+mod common;
+
 #[cfg(test)]
 mod detection_tests {
+    use crate::common;
     use dlib_face_recognition::Rectangle;
     use image::ImageBuffer;
     use std::fs;
     use uuid::Uuid;
     use video_sentry::detection;
     use video_sentry::detection::face_image::FaceImage;
+    use video_sentry::detection::options::Options;
     use video_sentry::detection::photo::Photo;
 
     #[test]
@@ -15,6 +19,7 @@ mod detection_tests {
         assert_eq!(item.image, None);
         assert_eq!(item.faces.len(), 0);
         assert_eq!(item.face_detected, false);
+        assert_eq!(item.orientation, 1);
         assert!(Uuid::parse_str(&item.uuid).is_ok(), "must be a uuid string");
     }
 
@@ -38,12 +43,45 @@ mod detection_tests {
         assert_eq!(item.image.unwrap(), image);
     }
 
+    #[test]
+    fn test_photo_store_orientation() {
+        let mut item = Photo::new();
+
+        item.store_orientation(6);
+
+        assert_eq!(item.orientation, 6);
+    }
+
+    #[test]
+    fn test_orientation_read() {
+        let orientation = detection::orientation::read(&"tests/fixtures/1.jpg".to_string());
+
+        assert_eq!(orientation, detection::orientation::NORMAL);
+    }
+
+    #[test]
+    fn test_orientation_apply() {
+        let image = image::DynamicImage::ImageLuma8(ImageBuffer::from_raw(2, 1, vec![0, 255]).unwrap());
+
+        let rotated = detection::orientation::apply(image.clone(), 6);
+        assert_eq!((rotated.width(), rotated.height()), (1, 2));
+        assert_eq!(rotated.to_luma8().into_raw(), vec![0, 255]);
+
+        let flipped = detection::orientation::apply(image.clone(), 2);
+        assert_eq!(flipped.to_luma8().into_raw(), vec![255, 0]);
+
+        assert_eq!(detection::orientation::apply(image.clone(), 1), image);
+        assert_eq!(detection::orientation::describe(8), "rotate 270");
+    }
+
     #[test]
     fn test_face_public_attributes() {
         let item = FaceImage::new();
 
         assert_eq!(item.face_location, None);
+        assert_eq!(item.confidence, None);
         assert_eq!(item.measurements.len(), 0);
+        assert_eq!(item.landmarks.len(), 0);
         assert!(Uuid::parse_str(&item.uuid).is_ok(), "must be a uuid string");
     }
 
@@ -72,6 +110,306 @@ mod detection_tests {
         assert_eq!(item.measurements.len(), 2);
     }
 
+    #[test]
+    fn test_face_image_store_landmarks() {
+        let mut item = FaceImage::new();
+        let landmarks: Vec<(i64, i64)> = vec![(812, 120), (815, 131)];
+
+        item.store_landmarks(landmarks.clone());
+
+        assert_eq!(item.landmarks, landmarks);
+    }
+
+    #[test]
+    fn test_crop_area() {
+        let rect = Rectangle {
+            left: 12,
+            top: 156,
+            right: 92,
+            bottom: 236,
+        };
+
+        assert_eq!(detection::crop_area(&rect, 0.0, 1000, 1000), rect);
+
+        let expected = Rectangle {
+            left: 0,
+            top: 140,
+            right: 108,
+            bottom: 250,
+        };
+        assert_eq!(detection::crop_area(&rect, 20.0, 1000, 250), expected);
+    }
+
+    #[test]
+    fn test_face_size() {
+        let rect = Rectangle {
+            left: 10,
+            top: 10,
+            right: 50,
+            bottom: 30,
+        };
+
+        assert_eq!(detection::face_size(&rect), 20);
+    }
+
+    #[test]
+    fn test_is_confident() {
+        let location = Rectangle {
+            left: 10,
+            top: 10,
+            right: 50,
+            bottom: 50,
+        };
+        let scored = detection::Detection {
+            location: location,
+            confidence: Some(0.7),
+        };
+        let unscored = detection::Detection {
+            location: location,
+            confidence: None,
+        };
+
+        assert_eq!(detection::is_confident(&scored, None), true);
+        assert_eq!(detection::is_confident(&scored, Some(0.5)), true);
+        assert_eq!(detection::is_confident(&scored, Some(0.8)), false);
+        assert_eq!(detection::is_confident(&unscored, Some(0.8)), true);
+    }
+
+    #[test]
+    fn test_format_score() {
+        assert_eq!(detection::format_score(Some(0.9876)), "0.99");
+        assert_eq!(detection::format_score(None), "n/a");
+    }
+
+    fn frontal_landmarks() -> Vec<(i64, i64)> {
+        let mut landmarks = vec![(0, 0); 68];
+        landmarks[0] = (0, 50);
+        landmarks[16] = (100, 50);
+        landmarks[8] = (50, 100);
+        landmarks[30] = (50, 56);
+        for i in 36..42 {
+            landmarks[i] = (30, 20);
+        }
+        for i in 42..48 {
+            landmarks[i] = (70, 20);
+        }
+        landmarks
+    }
+
+    #[test]
+    fn test_quality_head_pose() {
+        let (yaw, pitch, roll, inter_ocular_distance) =
+            detection::quality::head_pose(&frontal_landmarks());
+
+        assert!(yaw.abs() < 0.01);
+        assert!(pitch.abs() < 0.01);
+        assert!(roll.abs() < 0.01);
+        assert_eq!(inter_ocular_distance, 40.0);
+
+        let mut turned = frontal_landmarks();
+        turned[30] = (70, 56);
+        let (yaw, _, _, _) = detection::quality::head_pose(&turned);
+        assert!(yaw.abs() > 20.0);
+
+        assert_eq!(detection::quality::head_pose(&vec![]), (0.0, 0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_quality_laplacian_variance() {
+        let flat = image::GrayImage::from_pixel(10, 10, image::Luma([128]));
+        assert_eq!(detection::quality::laplacian_variance(&flat), 0.0);
+
+        let checkers = image::GrayImage::from_fn(10, 10, |x, y| image::Luma([((x + y) % 2 * 255) as u8]));
+        assert!(detection::quality::laplacian_variance(&checkers) > 0.0);
+    }
+
+    #[test]
+    fn test_quality_score() {
+        assert_eq!(detection::quality::score(0.0, 0.0, 150.0, 60.0, 128.0), 1.0);
+        assert_eq!(detection::quality::score(90.0, 0.0, 150.0, 60.0, 128.0), 0.0);
+        assert!(detection::quality::score(0.0, 0.0, 50.0, 60.0, 128.0) < 1.0);
+    }
+
+    #[test]
+    fn test_options_default() {
+        let options = detection::options::Options::default();
+
+        assert_eq!(options.crop_margin, 0.0);
+        assert_eq!(options.min_face_size, 40);
+        assert_eq!(options.min_confidence, None);
+        assert_eq!(options.nms_threshold, 0.5);
+        assert_eq!(options.jitters, 0);
+    }
+
+    #[test]
+    fn test_nms_iou() {
+        let a = Rectangle {
+            left: 0,
+            top: 0,
+            right: 10,
+            bottom: 10,
+        };
+        let b = Rectangle {
+            left: 5,
+            top: 0,
+            right: 15,
+            bottom: 10,
+        };
+        let c = Rectangle {
+            left: 20,
+            top: 20,
+            right: 30,
+            bottom: 30,
+        };
+
+        assert_eq!(detection::nms::iou(&a, &a), 1.0);
+        assert_eq!(detection::nms::iou(&a, &b), 50.0 / 150.0);
+        assert_eq!(detection::nms::iou(&a, &c), 0.0);
+    }
+
+    #[test]
+    fn test_nms_suppress() {
+        let detection = |left: i64, size: i64, confidence: Option<f64>| detection::Detection {
+            location: Rectangle {
+                left: left,
+                top: 0,
+                right: left + size,
+                bottom: size,
+            },
+            confidence: confidence,
+        };
+
+        let kept = detection::nms::suppress(
+            vec![
+                detection(100, 10, None),
+                detection(0, 10, Some(0.6)),
+                detection(1, 10, Some(0.9)),
+            ],
+            0.5,
+        );
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].location.left, 100);
+        assert_eq!(kept[1].confidence, Some(0.9));
+
+        let kept = detection::nms::suppress(vec![detection(0, 10, None), detection(1, 12, None)], 0.5);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].location.right, 13);
+
+        let kept = detection::nms::suppress(vec![detection(0, 10, None), detection(1, 12, None)], 0.9);
+        assert_eq!(kept.len(), 2);
+    }
+
+    #[test]
+    fn test_options_jitters() {
+        assert_eq!(detection::options::Options::library().jitters, 10);
+        assert_eq!(detection::options::Options::enrolment().jitters, 10);
+        assert_eq!(detection::options::Options::realtime().jitters, 0);
+    }
+
+    #[test]
+    fn test_analyze_with_fake_backend() {
+        let image = image::DynamicImage::ImageRgb8(ImageBuffer::from_pixel(200, 200, image::Rgb([128, 128, 128])));
+        let detections = vec![
+            common::detection(common::rectangle(10, 10, 80), Some(0.9)),
+            common::detection(common::rectangle(12, 12, 80), Some(0.5)),
+            common::detection(common::rectangle(120, 10, 10), Some(0.9)),
+            common::detection(common::rectangle(100, 100, 80), None),
+        ];
+        let embeddings = vec![vec![0.1; 128], vec![0.2; 128], vec![0.3; 128], vec![0.4; 128]];
+        let options = common::fake_backend("fake@1", detections, embeddings);
+
+        let photo = detection::analyze(image, &options);
+
+        assert_eq!(photo.faces.len(), 2);
+        assert_eq!(photo.faces[0].face_location.unwrap().left, 10);
+        assert_eq!(photo.faces[0].confidence, Some(0.9));
+        assert_eq!(photo.faces[0].measurements, vec![0.1; 128]);
+        assert_eq!(photo.faces[1].face_location.unwrap().left, 100);
+        assert_eq!(photo.faces[1].confidence, None);
+        assert_eq!(photo.faces[1].measurements, vec![0.4; 128]);
+
+        for face in photo.faces.iter() {
+            assert_eq!(face.landmarks.len(), 68);
+            let quality = face.quality.unwrap();
+            assert!(quality.yaw.abs() < 1.0);
+            assert!(quality.pitch.abs() < 1.0);
+            assert_eq!(quality.inter_ocular_distance, 32.0);
+        }
+    }
+
+    #[test]
+    fn test_photo_serialization() {
+        let image = image::DynamicImage::new_rgb8(200, 200);
+        let options = common::fake_backend(
+            "fake@1",
+            vec![common::detection(common::rectangle(10, 10, 80), Some(0.9))],
+            vec![],
+        );
+
+        let photo = detection::analyze(image, &options);
+        let json = serde_json::to_value(&photo).unwrap();
+
+        assert_eq!(json["uuid"], photo.uuid);
+        assert_eq!(json["format"], "jpeg");
+        assert!(json.get("image").is_none());
+
+        let face = &json["faces"][0];
+        assert_eq!(face["box"]["left"], 10);
+        assert_eq!(face["box"]["bottom"], 90);
+        assert_eq!(face["confidence"], 0.9);
+        assert_eq!(face["landmarks"].as_array().unwrap().len(), 68);
+        assert_eq!(face["measurements"].as_array().unwrap().len(), 128);
+        assert!(face["quality"]["score"].is_number());
+    }
+
+    #[test]
+    fn test_call_with_fake_backend() {
+        let options = Options {
+            min_face_size: 0,
+            ..common::fake_backend("fake@1", vec![common::detection(common::rectangle(0, 0, 1), None)], vec![])
+        };
+
+        let photo = detection::call_with_options(&"tests/fixtures/1px.jpg".to_string(), &options).unwrap();
+
+        assert_eq!(photo.faces.len(), 1);
+        assert_eq!(photo.faces[0].measurements.len(), 128);
+        assert!(fs::metadata(format!("storage/images/{}/{}.jpg", photo.uuid, photo.faces[0].uuid)).is_ok());
+
+        fs::remove_dir_all(format!("storage/images/{}", photo.uuid)).unwrap();
+    }
+
+    #[test]
+    fn test_annotate_with_fake_backend() {
+        let options = common::fake_locations(vec![]);
+        let photo = detection::call_with_options(&"tests/fixtures/1.jpg".to_string(), &options).unwrap();
+        assert_eq!(photo.faces.len(), 0);
+
+        let location = Rectangle {
+            left: 10,
+            top: 10,
+            right: 110,
+            bottom: 110,
+        };
+        let original_file_name = video_sentry::storage::original_file_name(photo.format);
+        let face = detection::annotate(&photo.uuid, &original_file_name, location, &options).unwrap();
+
+        assert_eq!(face.face_location.unwrap(), location);
+        assert_eq!(face.confidence, None);
+        assert_eq!(face.landmarks.len(), 68);
+        assert_eq!(face.measurements.len(), 128);
+        assert!(fs::metadata(format!("storage/images/{}/{}.jpg", photo.uuid, face.uuid)).is_ok());
+
+        let empty = Rectangle {
+            left: 10,
+            top: 10,
+            right: 10,
+            bottom: 110,
+        };
+        assert!(detection::annotate(&photo.uuid, &original_file_name, empty, &options).is_err());
+
+        fs::remove_dir_all(format!("storage/images/{}", photo.uuid)).unwrap();
+    }
+
     #[test]
     fn test_call() {
         let photo = detection::call(&"tests/fixtures/1.jpg".to_string()).unwrap();
@@ -478,6 +816,11 @@ mod detection_tests {
         assert_eq!(photo.faces[1].measurements, face_2_measurements);
         assert_eq!(photo.faces[2].measurements, face_3_measurements);
 
+        for face in photo.faces.iter() {
+            assert_eq!(face.landmarks.len(), 68);
+            assert!(face.quality.unwrap().inter_ocular_distance > 0.0);
+        }
+
         let face_1_location = photo.faces[0].face_location.unwrap();
         let expected_face_1_location = Rectangle {
             left: 812,
@@ -0,0 +1,128 @@
+mod common;
+
+use std::fs;
+use uuid::Uuid;
+use video_sentry::db::face::Face;
+use video_sentry::db::person::Person;
+use video_sentry::evaluator::{self, Probe};
+use video_sentry::recognition;
+use video_sentry::recognition::scoring::Strategy;
+
+fn probe(name: &str, predicted: Option<&str>, genuine: Option<f64>, impostors: Vec<f64>) -> Probe {
+    Probe {
+        path: format!("{}/1.jpg", name),
+        name: name.to_string(),
+        predicted: predicted.map(|person| person.to_string()),
+        genuine: genuine,
+        impostors: impostors,
+    }
+}
+
+#[test]
+fn test_report() {
+    common::setup();
+
+    let probes = vec![
+        probe("marion", Some("marion"), Some(0.3), vec![0.8]),
+        probe("armas", Some("marion"), Some(0.7), vec![0.5]),
+        probe("armas", None, Some(0.65), vec![0.9]),
+        probe("deniro", None, None, vec![0.7, 0.75]),
+    ];
+
+    let report = evaluator::report(probes, 0.6);
+    assert_eq!(report.probes, 4);
+    assert!((report.accuracy - 0.5).abs() < 1e-9);
+    assert!((report.precision - 0.5).abs() < 1e-9);
+    assert!((report.recall - 1.0 / 3.0).abs() < 1e-9);
+    assert!((report.far - 0.2).abs() < 1e-9);
+    assert!((report.frr - 2.0 / 3.0).abs() < 1e-9);
+
+    assert_eq!(report.roc.len(), 101);
+    assert_eq!(report.roc[0].far, 0.0);
+    assert_eq!(report.roc[0].frr, 1.0);
+    assert_eq!(report.roc[100].far, 1.0);
+    assert_eq!(report.roc[100].frr, 0.0);
+    // every genuine distance is accepted above 0.7, while only 2 of 5 impostors are
+    assert!((report.recommended_threshold - 0.71).abs() < 1e-9);
+    assert!(report.roc_csv().starts_with("threshold,far,frr\n0.00,0.0000,1.0000\n"));
+
+    // missing folders of the prefix are created
+    let prefix = "tmp/evaluator/reports/evaluation".to_string();
+    report.save(&prefix).unwrap();
+    assert!(fs::metadata(format!("{}_roc.csv", prefix)).is_ok());
+    fs::remove_dir_all("tmp/evaluator/").unwrap();
+    common::cleanup();
+}
+
+#[test]
+fn test_evaluate() {
+    common::setup();
+    common::pretrain();
+
+    let report = evaluator::call(&"tests/fixtures/people".to_string());
+    assert_eq!(report.probes, 3);
+    assert_eq!(report.skipped, 0);
+    assert_eq!(report.accuracy, 1.0);
+    assert!(report.results.iter().any(|probe| probe.name == "deniro" && probe.genuine.is_none()));
+
+    let prefix = "tmp/test/evaluation".to_string();
+    report.save(&prefix).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(format!("{}.json", prefix)).unwrap()).unwrap();
+    assert_eq!(json["probes"], 3);
+    assert_eq!(
+        fs::read_to_string(format!("{}_roc.csv", prefix)).unwrap(),
+        report.roc_csv()
+    );
+
+    common::cleanup();
+}
+
+#[test]
+fn test_evaluate_person_dropped_by_strategy() {
+    common::setup();
+
+    // the largest face is probed, smaller ones are enrolled
+    let detection_options = common::fake_backend(
+        "evaluated@1",
+        vec![
+            common::fake_detection(10),
+            common::fake_detection(200),
+            common::fake_detection(400),
+            common::detection(common::rectangle(600, 10, 150), None),
+        ],
+        vec![vec![0.1; 128], vec![0.3; 128], vec![0.32; 128], vec![0.31; 128]],
+    );
+    let detected = common::detect(&detection_options);
+    let labelled = Person::create(&Uuid::new_v4().to_string(), &"dropped_by_knn".to_string());
+    let other = Person::create(&Uuid::new_v4().to_string(), &"voted_by_knn".to_string());
+    Face::moderate_person(&detected.faces[0].uuid, &labelled.uuid);
+    Face::moderate_person(&detected.faces[1].uuid, &other.uuid);
+    Face::moderate_person(&detected.faces[2].uuid, &other.uuid);
+
+    fs::create_dir_all("tmp/evaluated/dropped_by_knn/").unwrap();
+    fs::copy("tests/fixtures/1.jpg", "tmp/evaluated/dropped_by_knn/1.jpg").unwrap();
+    // both closest faces vote for the other person, the labelled one is not a candidate
+    let recognition_options = recognition::options::Options {
+        strategy: Strategy::KnnVote,
+        top_k: 2,
+        ..Default::default()
+    };
+    let report = evaluator::call_with_options(
+        &"tmp/evaluated".to_string(),
+        &detection_options,
+        &recognition_options,
+    );
+
+    assert_eq!(report.probes, 1);
+    let genuine = report.results[0].genuine.unwrap();
+    assert!((genuine - 0.21 * 128f64.sqrt()).abs() < 1e-9);
+    assert_eq!(report.results[0].impostors.len(), 1);
+    assert_ne!(report.results[0].predicted.as_deref(), Some("dropped_by_knn"));
+    assert_eq!(report.accuracy, 0.0);
+
+    fs::remove_dir_all("tmp/evaluated/").unwrap();
+    Person::delete(&labelled.uuid);
+    Person::delete(&other.uuid);
+    common::remove_photos(&[&detected]);
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use image::codecs::gif::GifEncoder;
+use image::{DynamicImage, Frame, ImageFormat, RgbImage, Rgba, RgbaImage};
+use std::fs;
+use video_sentry::db::photo::Photo;
+use video_sentry::image_processor;
+use video_sentry::media;
+
+#[test]
+fn test_sniff() {
+    assert_eq!(
+        media::sniff(&"tests/fixtures/1.jpg".to_string()),
+        media::Kind::Image(ImageFormat::Jpeg)
+    );
+    assert_eq!(
+        media::sniff(&"Cargo.toml".to_string()),
+        media::Kind::Unsupported
+    );
+    assert_eq!(
+        media::sniff(&"tests/fixtures/missing.jpg".to_string()),
+        media::Kind::Unsupported
+    );
+}
+
+#[test]
+fn test_sniff_ignores_extension() {
+    common::setup();
+    fs::create_dir_all("./tmp/media/").unwrap();
+    let path = "./tmp/media/png.jpg".to_string();
+    DynamicImage::ImageRgb8(RgbImage::new(4, 4))
+        .save_with_format(&path, ImageFormat::Png)
+        .unwrap();
+
+    assert_eq!(media::sniff(&path), media::Kind::Image(ImageFormat::Png));
+    assert_eq!(media::open(&path).unwrap().width(), 4);
+
+    fs::remove_dir_all("./tmp/media/").unwrap();
+    common::cleanup();
+}
+
+#[test]
+fn test_names() {
+    for format in media::SUPPORTED_FORMATS {
+        assert_eq!(media::from_name(media::name(format)), Some(format));
+    }
+    assert_eq!(media::from_name("unknown"), None);
+    assert_eq!(media::extension(ImageFormat::Png), "png");
+    assert_eq!(media::extension(ImageFormat::Jpeg), "jpg");
+}
+
+#[test]
+fn test_animation_stores_one_frame() {
+    common::setup();
+    fs::create_dir_all("./tmp/animation/").unwrap();
+    let path = "./tmp/animation/animation.gif".to_string();
+    let frames = (0..3).map(|i| Frame::new(RgbaImage::from_pixel(20, 20, Rgba([i * 80, 0, 0, 255]))));
+    GifEncoder::new(fs::File::create(&path).unwrap())
+        .encode_frames(frames)
+        .unwrap();
+    assert_eq!(media::sniff(&path), media::Kind::AnimatedGif);
+
+    let options = common::fake_locations(vec![]);
+    let stored_before = Photo::where_all(&"detector = 'fake'".to_string()).len();
+    let result = image_processor::recognize_faces_with_options(&path, &options).unwrap();
+
+    assert!(!result.is_face_found());
+    assert_eq!(Photo::where_all(&"detector = 'fake'".to_string()).len(), stored_before + 1);
+    assert_eq!(Photo::find(&result.photo.uuid).detector, "fake");
+
+    fs::remove_dir_all("./tmp/animation/").unwrap();
+    common::remove_photos(&[&result.photo]);
+}
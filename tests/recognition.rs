@@ -0,0 +1,402 @@
+mod common;
+
+use uuid::Uuid;
+use video_sentry::db::face::Face;
+use video_sentry::db::person::Person;
+use video_sentry::recognition;
+use video_sentry::recognition::duplicates;
+use video_sentry::recognition::index;
+use video_sentry::recognition::outliers::{self, Reason};
+use video_sentry::recognition::scoring::{self, Strategy};
+use video_sentry::recognition::thresholds::{self, Thresholds};
+use video_sentry::recognition::verdict::{self, Verdict};
+
+fn person(name: &str) -> Person {
+    Person {
+        id: 0,
+        uuid: name.to_string(),
+        name: name.to_string(),
+        max_distance: None,
+    }
+}
+
+fn entry(person: Option<Person>, value: f64) -> index::Entry {
+    index::Entry {
+        face_uuid: Uuid::new_v4().to_string(),
+        person: person,
+        encoder: "fake@1".to_string(),
+        quality: None,
+        measurements: vec![value, 0.0],
+    }
+}
+
+// many faces of "crowd" at a medium distance, a single closest face of "single"
+fn scoring_entries() -> Vec<index::Entry> {
+    let mut entries = vec![entry(Some(person("single")), 0.1), entry(None, 0.15)];
+    for _ in 0..4 {
+        entries.push(entry(Some(person("crowd")), 0.2));
+    }
+    entries.push(entry(Some(person("crowd")), 0.9));
+    entries
+}
+
+fn rank(entries: &Vec<index::Entry>, strategy: Strategy) -> Vec<scoring::Candidate> {
+    let query = vec![0.0, 0.0];
+    let distances: Vec<(&index::Entry, f64)> = entries
+        .iter()
+        .map(|entry| (entry, index::distance(&entry.measurements, &query)))
+        .collect();
+
+    scoring::rank(&distances, &query, strategy, 5, 2, &thresholds(0.6))
+}
+
+fn thresholds(global: f64) -> Thresholds {
+    Thresholds {
+        global: global,
+        source: None,
+    }
+}
+
+#[test]
+fn test_scoring_strategies() {
+    let entries = scoring_entries();
+
+    let candidates = rank(&entries, Strategy::BestDistance);
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(candidates[0].person.name, "single");
+    assert!((candidates[0].score - 0.1).abs() < 1e-9);
+    assert!((candidates[0].margin.unwrap() - 0.1).abs() < 1e-9);
+    assert_eq!(candidates[1].margin, None);
+
+    // 3 of 5 nearest faces are "crowd", 1 is "single" and 1 has no person
+    let candidates = rank(&entries, Strategy::KnnVote);
+    assert_eq!(candidates[0].person.name, "crowd");
+    assert!((candidates[0].score - 0.4).abs() < 1e-9);
+    assert!((candidates[1].score - 0.8).abs() < 1e-9);
+    assert!((candidates[0].distance - 0.2).abs() < 1e-9);
+
+    let candidates = rank(&entries, Strategy::Centroid);
+    assert_eq!(candidates[0].person.name, "single");
+    assert!((candidates[1].score - 0.34).abs() < 1e-9);
+
+    let candidates = rank(&entries, Strategy::MeanTopN);
+    assert_eq!(candidates[0].person.name, "single");
+    assert!((candidates[1].score - 0.2).abs() < 1e-9);
+}
+
+#[test]
+fn test_strategy_min_margin() {
+    let options = recognition::options::Options {
+        min_margin: 0.05,
+        min_vote_margin: 0.2,
+        ..Default::default()
+    };
+    assert_eq!(options.strategy_min_margin(), 0.05);
+    let knn_options = recognition::options::Options {
+        strategy: Strategy::KnnVote,
+        ..options
+    };
+    assert_eq!(knn_options.strategy_min_margin(), 0.2);
+
+    // "crowd" leads by 2 of 5 votes, far more than a distance margin
+    let candidates = rank(&scoring_entries(), Strategy::KnnVote);
+    match verdict::decide(&candidates, knn_options.strategy_min_margin()) {
+        Verdict::Known(known, _) => assert_eq!(known.name, "crowd"),
+        other => panic!("unexpected verdict: {}", other),
+    }
+    assert!(matches!(verdict::decide(&candidates, 0.5), Verdict::Ambiguous(_)));
+}
+
+fn candidate(name: &str, score: f64) -> scoring::Candidate {
+    scoring::Candidate {
+        person: person(name),
+        score: score,
+        distance: score,
+        margin: None,
+        threshold: 0.6,
+    }
+}
+
+fn with_threshold(candidates: &[scoring::Candidate], threshold: f64) -> Vec<scoring::Candidate> {
+    candidates
+        .iter()
+        .map(|candidate| scoring::Candidate {
+            threshold: threshold,
+            ..candidate.clone()
+        })
+        .collect()
+}
+
+#[test]
+fn test_verdict_decide() {
+    assert_eq!(verdict::decide(&[], 0.05), Verdict::Unknown);
+    assert_eq!(verdict::decide(&[candidate("far", 0.7)], 0.05), Verdict::Unknown);
+
+    match verdict::decide(&[candidate("marion", 0.3), candidate("deniro", 0.5)], 0.05) {
+        Verdict::Known(person, confidence) => {
+            assert_eq!(person.name, "marion");
+            assert!((confidence - 0.5).abs() < 1e-9);
+        }
+        other => panic!("unexpected verdict: {}", other),
+    }
+
+    let close = [candidate("marion", 0.3), candidate("deniro", 0.32), candidate("far", 0.34)];
+    assert_eq!(
+        verdict::decide(&close, 0.05),
+        Verdict::Ambiguous(vec![person("marion"), person("deniro"), person("far")])
+    );
+    assert_eq!(
+        verdict::decide(&with_threshold(&close, 0.33), 0.05),
+        Verdict::Ambiguous(vec![person("marion"), person("deniro")])
+    );
+    assert_eq!(verdict::decide(&close, 0.05).to_string(), "ambiguous: marion, deniro, far");
+
+    // a strict threshold of the best person leaves the runner-up known
+    let mut strict = close.to_vec();
+    strict[0].threshold = 0.2;
+    strict[2].threshold = 0.2;
+    match verdict::decide(&strict, 0.05) {
+        Verdict::Known(person, confidence) => {
+            assert_eq!(person.name, "deniro");
+            assert!((confidence - (1.0 - 0.32 / 0.6)).abs() < 1e-9);
+        }
+        other => panic!("unexpected verdict: {}", other),
+    }
+}
+
+#[test]
+fn test_thresholds_precedence() {
+    let mut lenient = person("lenient");
+    lenient.max_distance = Some(0.7);
+
+    let global = thresholds(0.6);
+    assert_eq!(global.default_value(), 0.6);
+    assert_eq!(global.for_person(&person("plain")), 0.6);
+    assert_eq!(global.for_person(&lenient), 0.7);
+
+    let source = Thresholds {
+        global: 0.6,
+        source: Some(0.5),
+    };
+    assert_eq!(source.default_value(), 0.5);
+    assert_eq!(source.for_person(&person("plain")), 0.5);
+    assert_eq!(source.for_person(&lenient), 0.7);
+}
+
+#[test]
+fn test_thresholds_overrides() {
+    common::setup();
+
+    let name = format!("camera {}", Uuid::new_v4());
+    let options = recognition::options::Options {
+        max_distance: 0.55,
+        source: Some(name.clone()),
+        ..Default::default()
+    };
+    assert_eq!(Thresholds::load(&options).source, None);
+
+    thresholds::set("source", &name, "0.45").unwrap();
+    let loaded = Thresholds::load(&options);
+    assert_eq!(loaded.global, 0.55);
+    assert_eq!(loaded.source, Some(0.45));
+
+    thresholds::set("source", &name, "reset").unwrap();
+    assert_eq!(Thresholds::load(&options).source, None);
+
+    let person_name = format!("threshold {}", Uuid::new_v4());
+    let created = Person::create(&Uuid::new_v4().to_string(), &person_name);
+    thresholds::set("person", &person_name, "0.5").unwrap();
+    assert_eq!(Person::find(&created.uuid).max_distance, Some(0.5));
+    thresholds::set("person", &person_name, "reset").unwrap();
+    assert_eq!(Person::find(&created.uuid).max_distance, None);
+    assert!(thresholds::set("person", &person_name, "close").is_err());
+    assert!(thresholds::set("camera", &person_name, "0.5").is_err());
+    assert!(thresholds::set("person", &format!("nobody {}", Uuid::new_v4()), "0.5").is_err());
+
+    Person::delete(&created.uuid);
+    common::cleanup();
+}
+
+#[test]
+fn test_outliers() {
+    let mut entries = Vec::new();
+    for value in [0.0, 0.1, 0.05, 5.0] {
+        entries.push(entry(Some(person("anna")), value));
+    }
+    for value in [5.0, 5.1] {
+        entries.push(entry(Some(person("bob")), value));
+    }
+    for value in [20.0, 20.1, 20.05, 21.5] {
+        entries.push(entry(Some(person("carl")), value));
+    }
+    entries.push(entry(Some(person("dave")), 0.3));
+    entries.push(entry(None, 5.0));
+
+    let found = outliers::find(&entries, &thresholds(0.6));
+    assert_eq!(found.len(), 3);
+
+    // bob's face moderated as anna
+    assert_eq!(found[0].face_uuid, entries[3].face_uuid);
+    assert_eq!(found[0].reason, Reason::CloserToOther);
+    assert!((found[0].distance.unwrap() - 4.95).abs() < 1e-9);
+    assert_eq!(found[0].suggested_person, Some(person("bob")));
+    assert!((found[0].suggested_distance.unwrap() - 0.05).abs() < 1e-9);
+
+    // the only face of dave is closer to anna's faces than the threshold
+    assert_eq!(found[1].face_uuid, entries[10].face_uuid);
+    assert_eq!(found[1].reason, Reason::CloserToOther);
+    assert_eq!(found[1].distance, None);
+    assert_eq!(found[1].suggested_person, Some(person("anna")));
+
+    assert_eq!(found[2].face_uuid, entries[9].face_uuid);
+    assert_eq!(found[2].reason, Reason::FarFromPerson);
+    assert!((found[2].distance.unwrap() - 1.45).abs() < 1e-9);
+    assert_eq!(found[2].suggested_person, None);
+}
+
+#[test]
+fn test_duplicates() {
+    let persons = vec![
+        person("john_doe"),
+        person("John Doe"),
+        person("jon"),
+        person("anna"),
+        person("anna_b"),
+        person("bob"),
+    ];
+    let mut entries = Vec::new();
+    for (name, value) in [("john_doe", 0.0), ("john_doe", 0.1), ("jon", 0.05), ("anna", 3.0), ("bob", 3.2)] {
+        entries.push(entry(Some(person(name)), value));
+    }
+
+    let found = duplicates::find(&persons, &entries, &thresholds(0.6));
+    let pairs: Vec<(&str, &str, bool, bool)> = found
+        .iter()
+        .map(|duplicate| {
+            (
+                duplicate.person.name.as_str(),
+                duplicate.other.name.as_str(),
+                duplicate.close_faces,
+                duplicate.similar_names,
+            )
+        })
+        .collect();
+    assert_eq!(
+        pairs,
+        vec![
+            ("john_doe", "jon", true, false),
+            ("anna", "bob", true, false),
+            ("john_doe", "John Doe", false, true),
+            ("anna", "anna_b", false, true),
+        ]
+    );
+    assert!((found[0].distance.unwrap() - 0.05).abs() < 1e-9);
+    assert_eq!(found[2].distance, None);
+}
+
+#[test]
+fn test_similar_names() {
+    assert!(duplicates::similar_names("john_doe", "John Doe"));
+    assert!(duplicates::similar_names("john", "John Doe"));
+    assert!(duplicates::similar_names("jon doe", "john doe"));
+    assert!(!duplicates::similar_names("anna", "bob"));
+    assert!(!duplicates::similar_names("jon", "joe"));
+    assert!(!duplicates::similar_names("", ""));
+}
+
+#[test]
+fn test_strategy_parse() {
+    assert_eq!(Strategy::parse("best"), Strategy::BestDistance);
+    assert_eq!(Strategy::parse("knn"), Strategy::KnnVote);
+    assert_eq!(Strategy::parse("centroid"), Strategy::Centroid);
+    assert_eq!(Strategy::parse("mean_top_n"), Strategy::MeanTopN);
+}
+
+#[test]
+fn test_distance() {
+    assert_eq!(index::distance(&[0.0, 0.0], &[3.0, 4.0]), 5.0);
+    assert_eq!(index::distance(&[0.1; 128], &[0.1; 128]), 0.0);
+}
+
+#[test]
+fn test_index_remove() {
+    let mut index = index::Index::new(
+        ["a", "b", "c"]
+            .iter()
+            .map(|uuid| index::Entry {
+                face_uuid: uuid.to_string(),
+                person: None,
+                encoder: "removed@1".to_string(),
+                quality: None,
+                measurements: vec![0.5; 128],
+            })
+            .collect(),
+    );
+    let uuids = |index: &index::Index| {
+        let mut uuids: Vec<String> = index.entries().iter().map(|entry| entry.face_uuid.clone()).collect();
+        uuids.sort();
+        uuids
+    };
+
+    index.remove(&"a".to_string());
+    assert_eq!(uuids(&index), vec!["b", "c"]);
+    // the face moved into the place of the removed one is still found
+    index.remove(&"c".to_string());
+    assert_eq!(uuids(&index), vec!["b"]);
+    index.remove(&"missing".to_string());
+    assert_eq!(index.len(), 1);
+    assert_eq!(index.distances(&[0.5; 128], "removed@1", 0.0).len(), 1);
+}
+
+#[test]
+fn test_find_matches_from_index() {
+    common::setup();
+
+    let detected = common::detect(&common::fake_options("indexed@1", vec![0.1, 0.2, 0.3]));
+
+    let person = Person::create(&Uuid::new_v4().to_string(), &"indexed".to_string());
+    Face::moderate_person(&detected.faces[0].uuid, &person.uuid);
+    Face::moderate_person(&detected.faces[1].uuid, &person.uuid);
+
+    let recognition_options = recognition::options::Options {
+        top_k: 2,
+        ..Default::default()
+    };
+    let query = vec![0.12; 128];
+    let encoder = "indexed@1".to_string();
+    let matches = || {
+        recognition::find_matches_for_measurements(&query, &encoder, None, &recognition_options).matches
+    };
+
+    let result = matches();
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].0.as_ref().unwrap().uuid, person.uuid);
+    assert!(result[0].1 < result[1].1);
+
+    let candidates = recognition::find_matches_for_measurements(&query, &encoder, None, &recognition_options)
+        .candidates;
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].person.uuid, person.uuid);
+    assert!((candidates[0].distance - result[0].1).abs() < 1e-9);
+
+    // moderation reaches the loaded index
+    Face::moderate_person(&detected.faces[2].uuid, &person.uuid);
+    let indexed = index::with_index(|index| {
+        index
+            .entries()
+            .iter()
+            .filter(|entry| detected.faces.iter().any(|face| face.uuid == entry.face_uuid))
+            .count()
+    });
+    assert_eq!(indexed, 3);
+    assert_eq!(matches().len(), 2);
+
+    Face::delete(&detected.faces[0].uuid);
+    let result = matches();
+    assert!((result[0].1 - index::distance(&query, &[0.2; 128])).abs() < 1e-9);
+
+    Person::delete(&person.uuid);
+    assert!(matches()[0].0.is_none());
+
+    common::remove_photos(&[&detected]);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use dlib_face_recognition::Rectangle;
+use image::codecs::gif::GifEncoder;
+use image::{DynamicImage, Frame, GrayImage, Luma, Rgba, RgbaImage};
+use std::fs;
+use std::path::Path;
+use video_sentry::redactor;
+
+fn checkers() -> DynamicImage {
+    DynamicImage::ImageLuma8(GrayImage::from_fn(100, 100, |x, y| {
+        Luma([((x + y) % 2 * 255) as u8])
+    }))
+}
+
+#[test]
+fn test_redact_area() {
+    let location = Rectangle {
+        left: 40,
+        top: 40,
+        right: 60,
+        bottom: 60,
+    };
+
+    for style in [redactor::Style::Blur, redactor::Style::Pixelate] {
+        let original = checkers();
+        let mut image = original.clone();
+
+        redactor::redact_area(&mut image, &location, &style);
+
+        let original = original.to_luma8();
+        let image = image.to_luma8();
+        assert_ne!(image.get_pixel(50, 50), original.get_pixel(50, 50));
+        assert_eq!(image.get_pixel(10, 10), original.get_pixel(10, 10));
+        assert_eq!(image.get_pixel(90, 90), original.get_pixel(90, 90));
+    }
+}
+
+#[test]
+fn test_parse() {
+    assert!(matches!(redactor::Target::parse("all").unwrap(), redactor::Target::All));
+    assert!(matches!(redactor::Target::parse("unknown").unwrap(), redactor::Target::Unknown));
+    assert!(matches!(redactor::Style::parse("blur").unwrap(), redactor::Style::Blur));
+    assert!(matches!(redactor::Style::parse("pixelate").unwrap(), redactor::Style::Pixelate));
+    assert!(redactor::Target::parse("everyone").is_err());
+    assert!(redactor::Style::parse("paint").is_err());
+}
+
+#[test]
+fn test_parse_unknown_except() {
+    common::setup();
+    let error = redactor::Target::parse("except=Nobody").err().unwrap();
+    assert_eq!(error.to_string(), "Unknown persons: Nobody");
+    common::cleanup();
+}
+
+#[test]
+fn test_redact_animation_to_gif() {
+    common::setup();
+    fs::create_dir_all("./tmp/redacted/").unwrap();
+    let path = "./tmp/redacted/animation.gif".to_string();
+    let frames = (0..3).map(|i| Frame::new(RgbaImage::from_pixel(20, 20, Rgba([i * 80, 0, 0, 255]))));
+    GifEncoder::new(fs::File::create(&path).unwrap())
+        .encode_frames(frames)
+        .unwrap();
+
+    let output_path = "./tmp/redacted/redacted.GIF".to_string();
+    let result = redactor::call(&path, &output_path, &redactor::Target::All, &redactor::Style::Blur);
+    assert!(result.is_err());
+    assert!(!Path::new(&output_path).exists());
+
+    fs::remove_dir_all("./tmp/redacted/").unwrap();
+    common::cleanup();
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use uuid::Uuid;
+use video_sentry::db::face::Face;
+use video_sentry::db::person::Person;
+use video_sentry::db::photo::Photo;
+use video_sentry::recognition;
+use video_sentry::reencoder;
+
+#[test]
+fn test_reencode() {
+    common::setup();
+
+    let detected = common::detect(&common::fake_options("fake@1", vec![0.1]));
+    let face_uuid = detected.faces[0].uuid.clone();
+    let person = Person::create(&Uuid::new_v4().to_string(), &"reencoded".to_string());
+    Face::moderate_person(&face_uuid, &person.uuid);
+    assert_eq!(Face::find(&face_uuid).encoder, "fake@1");
+
+    let recognition_options = recognition::options::Options::from_env();
+    let query = vec![0.2; 128];
+    let matches = |encoder: &str| {
+        recognition::find_matches_for_measurements(&query, &encoder.to_string(), None, &recognition_options)
+            .matches
+    };
+    assert_eq!(matches("fake@1").len(), 1);
+    assert_eq!(matches("fake@2").len(), 0);
+
+    // landmarks are predicted again with the new measurements
+    Face::update(&face_uuid, vec![(&"landmarks".to_string(), &"[]".to_string())]);
+    // faces of a removed photo can't be re-encoded
+    let orphaned = common::detect(&common::fake_options("fake@1", vec![0.3]));
+    Photo::delete(&orphaned.uuid);
+
+    let summary = reencoder::call_with_options(&common::fake_options("fake@2", vec![0.2]));
+    assert!(summary.reencoded >= 1);
+    assert!(summary.failed >= 1);
+    assert_eq!(Face::find(&orphaned.faces[0].uuid).encoder, "fake@1");
+
+    let face = Face::find(&face_uuid);
+    assert_eq!(face.encoder, "fake@2");
+    assert_eq!(face.deserialize_measurements(), vec![0.2; 128]);
+    assert_eq!(face.deserialize_landmarks().len(), 68);
+    assert_eq!(face.person_uuid, person.uuid);
+    assert_eq!(matches("fake@1").len(), 0);
+    assert_eq!(matches("fake@2").len(), 1);
+
+    Face::delete(&orphaned.faces[0].uuid);
+    common::remove_photos(&[&detected, &orphaned]);
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use std::fs;
+use uuid::Uuid;
+use video_sentry::db::cluster::Cluster;
+use video_sentry::db::face::Face;
+use video_sentry::db::person::Person;
+use video_sentry::db::photo::Photo;
+use video_sentry::detection;
+use video_sentry::image_processor;
+use video_sentry::recognition::verdict::Verdict;
+use video_sentry::reprocessor;
+
+#[test]
+fn test_reconcile() {
+    let stored = vec![common::rectangle(0, 0, 100), common::rectangle(200, 0, 100)];
+    let detected = vec![
+        common::rectangle(205, 5, 100),
+        common::rectangle(500, 0, 100),
+        common::rectangle(5, 5, 100),
+        common::rectangle(0, 0, 100),
+    ];
+
+    assert_eq!(
+        reprocessor::reconcile(&stored, &detected, reprocessor::MIN_OVERLAP),
+        vec![Some(1), None, None, Some(0)]
+    );
+}
+
+#[test]
+fn test_reprocess() {
+    common::setup();
+
+    let detected = common::detect(&common::fake_locations(vec![common::rectangle(10, 10, 80), common::rectangle(200, 10, 80)]));
+
+    let moderated_uuid = detected.faces[0].uuid.clone();
+    let person = Person::create(&Uuid::new_v4().to_string(), &"reprocessed".to_string());
+    Face::moderate_person(&moderated_uuid, &person.uuid);
+
+    let options = common::fake_locations(vec![common::rectangle(12, 12, 80), common::rectangle(250, 150, 80)]);
+    let summary = reprocessor::call_with_options(
+        &reprocessor::Selection::Photos(vec![detected.uuid.clone()]),
+        &options,
+    );
+
+    assert_eq!(
+        summary,
+        reprocessor::Summary {
+            photos: 1,
+            failed: 0,
+            matched: 1,
+            added: 1,
+            removed: 1,
+        }
+    );
+
+    let faces = Photo::find(&detected.uuid).faces();
+    assert_eq!(faces.len(), 2);
+
+    let moderated = Face::find(&moderated_uuid);
+    assert_eq!(moderated.person_uuid, person.uuid);
+    assert_eq!(moderated.location(), common::rectangle(12, 12, 80));
+    for face in faces.iter() {
+        assert!(fs::metadata(format!("storage/images/{}/{}.jpg", detected.uuid, face.uuid)).is_ok());
+    }
+    assert!(fs::metadata(format!("storage/images/{}/{}.jpg", detected.uuid, detected.faces[1].uuid)).is_err());
+
+    fs::remove_dir_all(format!("storage/images/{}", detected.uuid)).unwrap();
+    let summary = reprocessor::call_with_options(
+        &reprocessor::Selection::Photos(vec![detected.uuid.clone()]),
+        &options,
+    );
+    assert_eq!(summary.failed, 1);
+    assert_eq!(
+        Photo::find(&detected.uuid).status(),
+        Some(detection::status::Status::Error)
+    );
+
+    common::cleanup();
+}
+
+#[test]
+fn test_reprocess_recognizes_unmoderated_faces() {
+    common::setup();
+
+    let options = common::fake_options("rerecognized@1", vec![0.5]);
+    let detected = common::detect(&options);
+
+    let face_uuid = &detected.faces[0].uuid;
+    let person = Person::create(&Uuid::new_v4().to_string(), &"stale".to_string());
+    Face::update_verdict(face_uuid, &Verdict::Known(person.clone(), 0.9), Some(0.42));
+    Face::update_person_uuid(face_uuid, &person.uuid);
+    Cluster::create(&Uuid::new_v4().to_string(), &vec![face_uuid.clone()]);
+
+    let summary = reprocessor::call_with_options(
+        &reprocessor::Selection::Photos(vec![detected.uuid.clone()]),
+        &options,
+    );
+    assert_eq!(summary.matched, 1);
+
+    // nobody is measured by the model, so the stale person is not kept
+    let face = Face::find(face_uuid);
+    assert_eq!(face.verdict, "unknown");
+    assert_eq!(face.person_uuid, "");
+    assert_ne!(face.threshold, Some(0.42));
+    assert_eq!(face.cluster_uuid, "");
+
+    common::remove_photos(&[&detected]);
+}
+
+#[test]
+fn test_reprocess_keeps_annotated_faces() {
+    common::setup();
+
+    let options = common::fake_options("annotated@1", vec![]);
+    let detected = common::detect(&options);
+    assert_eq!(Photo::find(&detected.uuid).status(), Some(detection::status::Status::NoFaces));
+
+    let face = image_processor::annotate_face_with_options(&detected.uuid, common::rectangle(10, 10, 100), &options).unwrap();
+    assert!(face.annotated);
+    let photo = Photo::find(&detected.uuid);
+    assert_eq!(photo.face_count, 1);
+    assert_eq!(photo.status(), Some(detection::status::Status::Ok));
+
+    let summary = reprocessor::call_with_options(
+        &reprocessor::Selection::Photos(vec![detected.uuid.clone()]),
+        &options,
+    );
+    assert_eq!(summary.removed, 0);
+    assert_eq!(Face::find(&face.uuid).location(), common::rectangle(10, 10, 100));
+    assert_eq!(Photo::find(&detected.uuid).face_count, 1);
+    assert!(fs::metadata(format!("storage/images/{}/{}.jpg", detected.uuid, face.uuid)).is_ok());
+
+    common::remove_photos(&[&detected]);
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use video_sentry::db::face::Face;
+use video_sentry::db::photo::Photo;
+use video_sentry::detection;
+use video_sentry::recognition::search::{self, Options};
+
+fn detect(embeddings: Vec<f64>) -> detection::photo::Photo {
+    common::detect(&common::fake_options("searched@1", embeddings))
+}
+
+#[test]
+fn test_search_by_face() {
+    common::setup();
+
+    let queried = detect(vec![0.5, 0.9]);
+    let close = detect(vec![0.52]);
+    let farther = detect(vec![0.55, 0.56]);
+    Photo::update(
+        &farther.uuid,
+        vec![(&"source".to_string(), &"Sam's door".to_string())],
+    );
+
+    let hits = search::by_face(&queried.faces[0].uuid, &Options::default()).unwrap();
+    let photos: Vec<&String> = hits.iter().map(|hit| &hit.photo_uuid).collect();
+    assert_eq!(photos, vec![&close.uuid, &farther.uuid]);
+    assert_eq!(hits[0].face_uuid, close.faces[0].uuid);
+    assert_eq!(hits[1].face_uuid, farther.faces[0].uuid);
+    assert!(hits[0].distance < hits[1].distance);
+    assert_eq!(hits[0].person, None);
+    assert!(!hits[0].created_at.is_empty());
+
+    let limited = Options {
+        limit: 1,
+        ..Default::default()
+    };
+    assert_eq!(search::by_face(&queried.faces[0].uuid, &limited).unwrap().len(), 1);
+
+    let by_source = Options {
+        source: Some("Sam's door".to_string()),
+        ..Default::default()
+    };
+    let hits = search::by_face(&queried.faces[0].uuid, &by_source).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].source, "Sam's door");
+
+    let before = Options {
+        to: Some("2000-01-01".to_string()),
+        ..Default::default()
+    };
+    assert!(search::by_face(&queried.faces[0].uuid, &before).unwrap().is_empty());
+    let since = Options {
+        from: Some("2000-01-01".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(search::by_face(&queried.faces[0].uuid, &since).unwrap().len(), 2);
+
+    // faces stored after the first search are found too
+    let closer = detect(vec![0.51]);
+    let hits = search::by_face(&queried.faces[0].uuid, &Options::default()).unwrap();
+    assert_eq!(hits[0].face_uuid, closer.faces[0].uuid);
+    Face::delete(&closer.faces[0].uuid);
+    let hits = search::by_face(&queried.faces[0].uuid, &Options::default()).unwrap();
+    assert_eq!(hits[0].face_uuid, close.faces[0].uuid);
+
+    // faces without measurements are left out until they are measured
+    let encoder = "searched@1".to_string();
+    Face::update_encoding(&close.faces[0].uuid, &Vec::new(), &Vec::new(), &encoder);
+    let hits = search::by_face(&queried.faces[0].uuid, &Options::default()).unwrap();
+    assert_eq!(hits[0].face_uuid, farther.faces[0].uuid);
+    Face::update_encoding(&close.faces[0].uuid, &vec![0.52; 128], &Vec::new(), &encoder);
+    let hits = search::by_face(&queried.faces[0].uuid, &Options::default()).unwrap();
+    assert_eq!(hits[0].face_uuid, close.faces[0].uuid);
+
+    common::remove_photos(&[&queried, &close, &farther, &closer]);
+}
+
+#[test]
+fn test_search_by_image() {
+    common::setup();
+    common::pretrain();
+
+    let hits = search::by_image(&"tests/fixtures/people/marion/2.jpg".to_string(), &Options::default()).unwrap();
+    assert!(!hits.is_empty());
+    assert_eq!(hits[0].person.as_ref().unwrap().name, "marion");
+
+    assert!(search::by_image(&"tests/fixtures/1px.jpg".to_string(), &Options::default()).is_err());
+    assert!(search::by_face(&"missing".to_string(), &Options::default()).is_err());
+
+    common::cleanup();
+}
+
+#[test]
+fn test_parse_date() {
+    assert_eq!(search::parse_date("2024-02-29").unwrap(), "2024-02-29");
+    assert!(search::parse_date("2023-02-29").is_err());
+    assert!(search::parse_date("2023-13-01").is_err());
+    assert!(search::parse_date("2023-1-01").is_err());
+    assert!(search::parse_date("yesterday").is_err());
+    assert!(search::parse_date("2023-01-01' OR '1").is_err());
+}
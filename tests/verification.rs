@@ -0,0 +1,89 @@
+mod common;
+
+use uuid::Uuid;
+use video_sentry::db::face::Face;
+use video_sentry::db::person::Person;
+use video_sentry::recognition::options::Options;
+use video_sentry::recognition::verification;
+
+#[test]
+fn test_verify_faces() {
+    common::setup();
+
+    let detected = common::detect(&common::fake_options("verified@1", vec![0.1, 0.12, 0.9]));
+    let options = Options::default();
+
+    let same = verification::faces_with_options(&detected.faces[0].uuid, &detected.faces[1].uuid, &options).unwrap();
+    assert!(same.same_person);
+    assert!((same.distance - 0.02 * 128f64.sqrt()).abs() < 1e-9);
+    assert_eq!(same.threshold, 0.6);
+    assert_eq!(same.left.input, detected.faces[0].uuid);
+    assert_eq!(same.right.input, detected.faces[1].uuid);
+
+    let different =
+        verification::faces_with_options(&detected.faces[0].uuid, &detected.faces[2].uuid, &options).unwrap();
+    assert!(!different.same_person);
+
+    // the stricter threshold of both persons is used, whichever side they are on
+    let strict = Person::create(&Uuid::new_v4().to_string(), &"Strict".to_string());
+    let lenient = Person::create(&Uuid::new_v4().to_string(), &"Lenient".to_string());
+    Person::set_max_distance(&strict.uuid, Some(0.3));
+    Person::set_max_distance(&lenient.uuid, Some(0.5));
+    Face::moderate_person(&detected.faces[0].uuid, &lenient.uuid);
+    Face::moderate_person(&detected.faces[1].uuid, &strict.uuid);
+    let verification =
+        verification::faces_with_options(&detected.faces[0].uuid, &detected.faces[1].uuid, &options).unwrap();
+    assert_eq!(verification.threshold, 0.3);
+    assert!(verification::faces_with_options(&detected.faces[0].uuid, &"missing".to_string(), &options).is_err());
+
+    for face in detected.faces.iter() {
+        Face::delete(&face.uuid);
+    }
+    Person::delete(&strict.uuid);
+    Person::delete(&lenient.uuid);
+
+    common::remove_photos(&[&detected]);
+}
+
+#[test]
+fn test_verify_images() {
+    common::setup();
+
+    let path = "tests/fixtures/1.jpg".to_string();
+    let verification =
+        verification::images_with_options(&path, &path, &common::fake_options("verified_images@1", vec![0.1, 0.5]), &Options::default()).unwrap();
+    assert!(verification.same_person);
+    assert_eq!(verification.distance, 0.0);
+    assert_eq!(verification.left.faces, 2);
+    assert_eq!(verification.left.face, verification.right.face);
+    assert!(verification.left.location.is_some());
+    // nothing is stored
+    assert!(Face::where_all(&"encoder = 'verified_images@1'".to_string()).is_empty());
+
+    let json = serde_json::to_value(&verification).unwrap();
+    assert_eq!(json["same_person"], true);
+    assert_eq!(json["left"]["box"]["top"], 10);
+
+    assert!(verification::images_with_options(&path, &path, &common::fake_options("verified_images@1", vec![]), &Options::default()).is_err());
+
+    common::cleanup();
+}
+
+#[test]
+fn test_verify_largest_faces() {
+    common::setup();
+
+    let options = common::fake_backend(
+        "verified_largest@1",
+        vec![common::fake_detection(10), common::detection(common::rectangle(200, 10, 150), None)],
+        vec![vec![0.1; 128], vec![0.5; 128]],
+    );
+
+    let path = "tests/fixtures/1.jpg".to_string();
+    let verification = verification::images_with_options(&path, &path, &options, &Options::default()).unwrap();
+    assert_eq!(verification.left.face, 1);
+    assert_eq!(verification.right.face, 1);
+    assert_eq!(verification.left.location, Some(common::rectangle(200, 10, 150)));
+
+    common::cleanup();
+}
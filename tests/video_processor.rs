@@ -1,5 +1,7 @@
 mod common;
 
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
 use video_sentry::db::photo::Photo;
 use video_sentry::db::person::Person;
 use video_sentry::db::face::Face;
@@ -24,4 +26,28 @@ fn video_processor() {
     assert_eq!(face_count_diff, 1);
 
     common::cleanup();
-}
\ No newline at end of file
+}
+#[test]
+fn test_each_frame_removes_frames() {
+    let path = "tests/fixtures/video/1.mp4".to_string();
+
+    let mut folder = PathBuf::new();
+    video_sentry::video_processor::each_frame(&path, |path_to_image| {
+        folder = Path::new(path_to_image).parent().unwrap().to_path_buf();
+        true
+    })
+    .unwrap();
+    assert!(!folder.as_os_str().is_empty());
+    assert!(!folder.exists());
+
+    // also when processing of a frame fails
+    let mut folder = PathBuf::new();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        video_sentry::video_processor::each_frame(&path, |path_to_image| {
+            folder = Path::new(path_to_image).parent().unwrap().to_path_buf();
+            panic!("frame can't be processed");
+        })
+    }));
+    assert!(result.is_err());
+    assert!(!folder.exists());
+}